@@ -0,0 +1,9 @@
+use std::io;
+
+use chess::engine::uci::Uci;
+
+/// The engine on its own, for chess GUIs and match runners that speak UCI.
+fn main() {
+    let mut uci = Uci::new(io::stdout());
+    uci.run(io::stdin().lock());
+}
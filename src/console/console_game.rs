@@ -0,0 +1,148 @@
+use std::io;
+
+use crate::console::players::{Player, Players};
+use crate::game::game::Game;
+
+/// A game in the console and who plays each of its colors. The computer plays as soon as it's
+/// its turn, everything else is read from the keyboard.
+pub struct ConsoleGame {
+    game: Game,
+    players: Players,
+}
+
+impl ConsoleGame {
+    pub fn new(players: Players) -> ConsoleGame {
+        ConsoleGame {
+            game: Game::new(),
+            players,
+        }
+    }
+
+    pub fn get_game(&self) -> &Game {
+        &self.game
+    }
+
+    pub fn get_players(&self) -> &Players {
+        &self.players
+    }
+
+    /// Same as `Game::handle_input` plus `new`, which starts over with other players.
+    /// `undo` and `redo` take back or play again the computer's reply too.
+    pub fn handle_input(&mut self, input: &str) -> Result<(), String> {
+        let mut splitted = input.split(" ");
+        match splitted.next() {
+            Some("new") => {
+                self.players = Players::parse(splitted)?;
+                self.game = Game::new();
+                println!("New game.");
+                self.game.print_status();
+                Ok(())
+            }
+            Some("undo") => {
+                self.game.undo()?;
+                // or the computer would just play it again.
+                if self.is_computer_turn() {
+                    self.game.undo().ok();
+                }
+                self.game.print_status();
+                Ok(())
+            }
+            Some("redo") => {
+                self.game.redo()?;
+                if self.is_computer_turn() {
+                    self.game.redo().ok();
+                }
+                self.game.print_status();
+                Ok(())
+            }
+            Some("help") => {
+                self.game.handle_input(input)?;
+                println!("new [white|black|both|none] [depth N|time S]: Starts a new game where");
+                println!("     the computer plays the colors given, N moves ahead or S seconds.");
+                println!("     e.g.: new black depth 4. Just new for two players.");
+                println!();
+                Ok(())
+            }
+            _ => self.game.handle_input(input),
+        }
+    }
+
+    fn is_computer_turn(&self) -> bool {
+        !self.game.is_over() && self.players.is_computer(self.game.get_current_color())
+    }
+
+    /// Lets the computer play its move, if it's its turn.
+    fn play_computer_move(&mut self) {
+        let limits = match self.players.get(self.game.get_current_color()) {
+            Player::Computer(limits) => limits.clone(),
+            Player::Human => return,
+        };
+        let movement = match self.game.best_move(&limits) {
+            None => return,
+            Some(movement) => movement,
+        };
+        let san = self.game.to_san(&movement);
+        let color = self.game.get_current_user_color();
+        self.game
+            .play_movement(&movement)
+            .expect("the computer only plays legal movements");
+        println!("{} plays {}", color, san);
+        self.game.print_status();
+    }
+
+    pub fn read_input(&mut self) {
+        println!("Welcome!");
+        println!("Type in help if you're new to this console game.");
+        println!("White moves first. Waiting on next action...");
+        while !self.game.is_over() {
+            self.game.print_board();
+            if self.is_computer_turn() {
+                self.play_computer_move();
+                continue;
+            }
+            let mut user_input = String::new();
+            match io::stdin().read_line(&mut user_input) {
+                Err(_) => print!("Failed to read input. try again"),
+                Ok(_) => {
+                    if let Err(error) = self.handle_input(user_input.trim()) {
+                        println!("{}", error)
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::console::console_game::*;
+    use crate::game::fen::STARTING_POSITION;
+    use crate::pieces::color::Color;
+
+    #[test]
+    fn new_game_against_the_computer() {
+        let mut console = ConsoleGame::new(Players::humans());
+        console.handle_input("e4").unwrap();
+        console.handle_input("new black depth 1").unwrap();
+        assert_eq!(STARTING_POSITION, console.get_game().to_fen());
+        assert_eq!(false, console.get_players().is_computer(&Color::White));
+        assert_eq!(true, console.get_players().is_computer(&Color::Black));
+        assert_eq!(false, console.is_computer_turn());
+
+        console.handle_input("e4").unwrap();
+        assert_eq!(true, console.is_computer_turn());
+        console.play_computer_move();
+        assert_eq!(Color::White, *console.get_game().get_current_color());
+        assert_eq!(2, console.get_game().get_movements().len());
+
+        // undo takes back the computer's reply too.
+        console.handle_input("undo").unwrap();
+        assert_eq!(STARTING_POSITION, console.get_game().to_fen());
+        console.handle_input("redo").unwrap();
+        assert_eq!(2, console.get_game().get_movements().len());
+
+        assert_eq!(true, console.handle_input("new purple").is_err());
+        console.handle_input("new").unwrap();
+        assert_eq!(false, console.get_players().is_computer(&Color::Black));
+    }
+}
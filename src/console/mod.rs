@@ -0,0 +1,2 @@
+pub mod console_game;
+pub mod players;
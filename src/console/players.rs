@@ -0,0 +1,133 @@
+use std::time::Duration;
+
+use crate::engine::search::SearchLimits;
+use crate::pieces::color::Color;
+
+// how far the computer looks when it isn't told.
+pub const DEFAULT_DEPTH: u32 = 4;
+
+/// Who moves the pieces of a color in the console game.
+#[derive(Clone, Debug)]
+pub enum Player {
+    Human,
+    // the computer thinks until one of the limits is reached.
+    Computer(SearchLimits),
+}
+
+#[derive(Clone, Debug)]
+pub struct Players {
+    white: Player,
+    black: Player,
+}
+
+impl Players {
+    /// Two people at the same keyboard.
+    pub fn humans() -> Players {
+        Players {
+            white: Player::Human,
+            black: Player::Human,
+        }
+    }
+
+    pub fn new(white: Player, black: Player) -> Players {
+        Players { white, black }
+    }
+
+    pub fn get(&self, color: &Color) -> &Player {
+        match color {
+            Color::White => &self.white,
+            Color::Black => &self.black,
+        }
+    }
+
+    pub fn is_computer(&self, color: &Color) -> bool {
+        matches!(self.get(color), Player::Computer(_))
+    }
+
+    /// Reads which colors the computer plays and how long it thinks, e.g. `black depth 5` or
+    /// `both time 2`. The color is `white`, `black`, `both` or `none`, the time is in seconds.
+    /// Nothing at all means two humans.
+    pub fn parse<'a>(mut options: impl Iterator<Item = &'a str>) -> Result<Players, String> {
+        let mut computer_colors = vec![];
+        let mut limits = SearchLimits::depth(DEFAULT_DEPTH);
+        while let Some(option) = options.next() {
+            match option {
+                "white" => computer_colors = vec![Color::White],
+                "black" => computer_colors = vec![Color::Black],
+                "both" => computer_colors = vec![Color::White, Color::Black],
+                "none" => computer_colors = vec![],
+                "depth" => match options.next().map(|depth| depth.parse::<u32>()) {
+                    Some(Ok(depth)) if depth > 0 => limits = SearchLimits::depth(depth),
+                    _ => return Err(String::from("Invalid depth. e.g. depth 4")),
+                },
+                "time" => match options.next().map(|time| time.parse::<f64>()) {
+                    Some(Ok(time)) if time > 0.0 && time.is_finite() => {
+                        limits = SearchLimits::time(Duration::from_secs_f64(time))
+                    }
+                    _ => return Err(String::from("Invalid time. e.g. time 2.5")),
+                },
+                "" => {}
+                _ => {
+                    return Err(format!(
+                    "Unknown option {}. Use white, black, both or none, then depth N or time S.",
+                    option
+                ))
+                }
+            }
+        }
+        let player = |color: Color| match computer_colors.contains(&color) {
+            true => Player::Computer(limits.clone()),
+            false => Player::Human,
+        };
+        Ok(Players::new(player(Color::White), player(Color::Black)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use crate::console::players::*;
+
+    #[test]
+    fn nothing_means_two_humans() {
+        let players = Players::parse("".split(' ')).unwrap();
+        assert_eq!(false, players.is_computer(&Color::White));
+        assert_eq!(false, players.is_computer(&Color::Black));
+    }
+
+    #[test]
+    fn computer_colors_and_limits() {
+        let players = Players::parse("black".split(' ')).unwrap();
+        assert_eq!(false, players.is_computer(&Color::White));
+        match players.get(&Color::Black) {
+            Player::Computer(limits) => assert_eq!(Some(DEFAULT_DEPTH), limits.depth),
+            Player::Human => panic!("black should be the computer"),
+        }
+
+        let players = Players::parse("both time 1.5".split(' ')).unwrap();
+        for color in [Color::White, Color::Black] {
+            match players.get(&color) {
+                Player::Computer(limits) => {
+                    assert_eq!(Some(Duration::from_millis(1500)), limits.time);
+                    assert_eq!(None, limits.depth);
+                }
+                Player::Human => panic!("both should be the computer"),
+            }
+        }
+
+        let players = Players::parse(["depth", "2", "white"].into_iter()).unwrap();
+        match players.get(&Color::White) {
+            Player::Computer(limits) => assert_eq!(Some(2), limits.depth),
+            Player::Human => panic!("white should be the computer"),
+        }
+    }
+
+    #[test]
+    fn invalid_options() {
+        assert!(Players::parse("red".split(' ')).is_err());
+        assert!(Players::parse("black depth".split(' ')).is_err());
+        assert!(Players::parse("black depth 0".split(' ')).is_err());
+        assert!(Players::parse("black time soon".split(' ')).is_err());
+    }
+}
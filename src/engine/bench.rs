@@ -0,0 +1,50 @@
+use crate::engine::search::{SearchLimits, SearchResult};
+use crate::engine::transposition::TranspositionTable;
+use crate::game::fen::STARTING_POSITION;
+use crate::game::game::Game;
+
+// the perft positions and a quiet opening, a bit of everything.
+pub const BENCH_POSITIONS: [&str; 7] = [
+    STARTING_POSITION,
+    "r1bqkbnr/pppp1ppp/2n5/4p3/4P3/5N2/PPPP1PPP/RNBQKB1R w KQkq - 2 3",
+    "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+    "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1",
+    "r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1",
+    "rnbq1k1r/pp1Pbppp/2p5/8/2B5/8/PPP1NnPP/RNBQK2R w KQ - 1 8",
+    "r4rk1/1pp1qppp/p1np1n2/2b1p1B1/2B1P1b1/P1NP1N2/1PP1QPPP/R4RK1 w - - 0 10",
+];
+
+// small and the same every time so the node counts are too.
+const BENCH_HASH_SIZE: usize = 4;
+
+/// Searches every bench position to `depth` from scratch. The node counts only change when the
+/// search does, so they show how much a change to it helps.
+pub fn bench(depth: u32) -> Vec<(&'static str, SearchResult)> {
+    BENCH_POSITIONS
+        .iter()
+        .map(|fen| {
+            let game = Game::from_fen(fen).expect("the bench positions are valid");
+            let mut table = TranspositionTable::new(BENCH_HASH_SIZE);
+            let result = game.search_with_info(&SearchLimits::depth(depth), &mut table, |_| {});
+            (*fen, result)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::engine::bench::*;
+
+    #[test]
+    fn node_counts_dont_change_between_runs() {
+        let first = bench(2);
+        let second = bench(2);
+        assert_eq!(BENCH_POSITIONS.len(), first.len());
+        for ((fen, first), (_, second)) in first.iter().zip(second.iter()) {
+            assert_eq!(2, first.depth, "{}", fen);
+            assert_eq!(first.nodes, second.nodes, "{}", fen);
+            assert!(first.quiescence_nodes < first.nodes, "{}", fen);
+            assert_eq!(first.best_move, second.best_move, "{}", fen);
+        }
+    }
+}
@@ -0,0 +1,174 @@
+use crate::game::bitboard::Bitboard;
+use crate::game::board::Board;
+use crate::pieces::color::Color;
+use crate::pieces::piece::PieceType;
+
+// the tables below are from white's side and in the same order as `squares`, a8 first.
+// black looks them up mirrored.
+
+#[rustfmt::skip]
+const PAWN_TABLE: [i32; 64] = [
+     0,  0,  0,  0,  0,  0,  0,  0,
+    50, 50, 50, 50, 50, 50, 50, 50,
+    10, 10, 20, 30, 30, 20, 10, 10,
+     5,  5, 10, 25, 25, 10,  5,  5,
+     0,  0,  0, 20, 20,  0,  0,  0,
+     5, -5,-10,  0,  0,-10, -5,  5,
+     5, 10, 10,-20,-20, 10, 10,  5,
+     0,  0,  0,  0,  0,  0,  0,  0,
+];
+
+#[rustfmt::skip]
+const KNIGHT_TABLE: [i32; 64] = [
+    -50,-40,-30,-30,-30,-30,-40,-50,
+    -40,-20,  0,  0,  0,  0,-20,-40,
+    -30,  0, 10, 15, 15, 10,  0,-30,
+    -30,  5, 15, 20, 20, 15,  5,-30,
+    -30,  0, 15, 20, 20, 15,  0,-30,
+    -30,  5, 10, 15, 15, 10,  5,-30,
+    -40,-20,  0,  5,  5,  0,-20,-40,
+    -50,-40,-30,-30,-30,-30,-40,-50,
+];
+
+#[rustfmt::skip]
+const BISHOP_TABLE: [i32; 64] = [
+    -20,-10,-10,-10,-10,-10,-10,-20,
+    -10,  0,  0,  0,  0,  0,  0,-10,
+    -10,  0,  5, 10, 10,  5,  0,-10,
+    -10,  5,  5, 10, 10,  5,  5,-10,
+    -10,  0, 10, 10, 10, 10,  0,-10,
+    -10, 10, 10, 10, 10, 10, 10,-10,
+    -10,  5,  0,  0,  0,  0,  5,-10,
+    -20,-10,-10,-10,-10,-10,-10,-20,
+];
+
+#[rustfmt::skip]
+const ROOK_TABLE: [i32; 64] = [
+     0,  0,  0,  0,  0,  0,  0,  0,
+     5, 10, 10, 10, 10, 10, 10,  5,
+    -5,  0,  0,  0,  0,  0,  0, -5,
+    -5,  0,  0,  0,  0,  0,  0, -5,
+    -5,  0,  0,  0,  0,  0,  0, -5,
+    -5,  0,  0,  0,  0,  0,  0, -5,
+    -5,  0,  0,  0,  0,  0,  0, -5,
+     0,  0,  0,  5,  5,  0,  0,  0,
+];
+
+#[rustfmt::skip]
+const QUEEN_TABLE: [i32; 64] = [
+    -20,-10,-10, -5, -5,-10,-10,-20,
+    -10,  0,  0,  0,  0,  0,  0,-10,
+    -10,  0,  5,  5,  5,  5,  0,-10,
+     -5,  0,  5,  5,  5,  5,  0, -5,
+      0,  0,  5,  5,  5,  5,  0, -5,
+    -10,  5,  5,  5,  5,  5,  0,-10,
+    -10,  0,  5,  0,  0,  0,  0,-10,
+    -20,-10,-10, -5, -5,-10,-10,-20,
+];
+
+// keeps the king behind its pawns, it doesn't know about endgames yet.
+#[rustfmt::skip]
+const KING_TABLE: [i32; 64] = [
+    -30,-40,-40,-50,-50,-40,-40,-30,
+    -30,-40,-40,-50,-50,-40,-40,-30,
+    -30,-40,-40,-50,-50,-40,-40,-30,
+    -30,-40,-40,-50,-50,-40,-40,-30,
+    -20,-30,-30,-40,-40,-30,-30,-20,
+    -10,-20,-20,-20,-20,-20,-20,-10,
+     20, 20,  0,  0,  0,  0, 20, 20,
+     20, 30, 10,  0,  0, 10, 30, 20,
+];
+
+fn get_table(piece_type: &PieceType) -> &'static [i32; 64] {
+    match piece_type {
+        PieceType::Pawn => &PAWN_TABLE,
+        PieceType::Knight => &KNIGHT_TABLE,
+        PieceType::Bishop => &BISHOP_TABLE,
+        PieceType::Rook => &ROOK_TABLE,
+        PieceType::Queen => &QUEEN_TABLE,
+        PieceType::King => &KING_TABLE,
+    }
+}
+
+/// How much the piece is worth on that square, counting where it stands.
+/// `square` is the bitboard index, a8 is 0.
+pub fn get_square_value(color: &Color, piece_type: &PieceType, square: usize) -> i32 {
+    let square = match color {
+        Color::White => square,
+        // same column, mirrored row.
+        Color::Black => square ^ 56,
+    };
+    piece_type.get_value() + get_table(piece_type)[square]
+}
+
+fn evaluate_color(board: &Board, color: &Color) -> i32 {
+    let bitboards = board.get_bitboards();
+    let mut score = 0;
+    for piece_type in [
+        PieceType::Pawn,
+        PieceType::Knight,
+        PieceType::Bishop,
+        PieceType::Rook,
+        PieceType::Queen,
+        PieceType::King,
+    ] {
+        let mut pieces: Bitboard = bitboards.get(color, &piece_type);
+        while pieces != 0 {
+            let square = pieces.trailing_zeros() as usize;
+            score += get_square_value(color, &piece_type, square);
+            pieces &= pieces - 1;
+        }
+    }
+    score
+}
+
+/// The position in centipawns from the point of view of `color`, positive when it's ahead.
+pub fn evaluate(board: &Board, color: &Color) -> i32 {
+    evaluate_color(board, color) - evaluate_color(board, &color.opposite())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::engine::evaluation::*;
+    use crate::game::game::Game;
+
+    #[test]
+    fn starting_position_is_even() {
+        let game = Game::new();
+        assert_eq!(0, evaluate(game.get_board(), &Color::White));
+        assert_eq!(0, evaluate(game.get_board(), &Color::Black));
+    }
+
+    #[test]
+    fn mirrored_positions_score_the_same() {
+        let white = Game::from_fen("4k3/8/8/3n4/8/8/1P3PP1/R3K1N1 w - - 0 1").unwrap();
+        let black = Game::from_fen("r3k1n1/1p3pp1/8/8/3N4/8/8/4K3 b - - 0 1").unwrap();
+        let score = evaluate(white.get_board(), &Color::White);
+        assert_eq!(score, evaluate(black.get_board(), &Color::Black));
+        assert_eq!(-score, evaluate(white.get_board(), &Color::Black));
+    }
+
+    #[test]
+    fn material_counts_the_most() {
+        // black is missing its queen
+        let game =
+            Game::from_fen("rnb1kbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1").unwrap();
+        let score = evaluate(game.get_board(), &Color::White);
+        assert!(score > 850, "{}", score);
+    }
+
+    #[test]
+    fn pieces_prefer_the_center() {
+        let d4 = 4 * 8 + 3;
+        let a1 = 7 * 8;
+        assert!(
+            get_square_value(&Color::White, &PieceType::Knight, d4)
+                > get_square_value(&Color::White, &PieceType::Knight, a1)
+        );
+        // e7 is as good for a black pawn as e2 for a white one.
+        assert_eq!(
+            get_square_value(&Color::White, &PieceType::Pawn, 6 * 8 + 4),
+            get_square_value(&Color::Black, &PieceType::Pawn, 8 + 4)
+        );
+    }
+}
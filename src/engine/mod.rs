@@ -0,0 +1,8 @@
+pub mod bench;
+pub mod evaluation;
+pub mod ordering;
+pub mod search;
+pub mod time;
+pub mod transposition;
+pub mod uci;
+pub mod xboard;
@@ -0,0 +1,212 @@
+use crate::engine::search::MAX_PLY;
+use crate::game::bitboard::{color_index, piece_index, square_index, Bitboards};
+use crate::game::movement::Movement;
+use crate::pieces::color::Color;
+use crate::pieces::piece::PieceType;
+
+// the order movements are tried in, the best first.
+const HASH_MOVE: i32 = 1_000_000;
+const CAPTURE: i32 = 100_000;
+const PROMOTION: i32 = 90_000;
+const FIRST_KILLER: i32 = 80_000;
+const SECOND_KILLER: i32 = 79_000;
+// quiet movements are ordered by their history, which stays under the killers.
+const MAX_HISTORY: i32 = 50_000;
+
+/// Whether the movement takes a piece, en passant included.
+pub fn is_capture(bitboards: &Bitboards, movement: &Movement) -> bool {
+    bitboards.get_piece(&movement.to).is_some()
+        || (movement.from.column != movement.to.column
+            && matches!(
+                bitboards.get_piece(&movement.from),
+                Some((_, PieceType::Pawn))
+            ))
+}
+
+/// Most valuable victim, least valuable attacker: taking a queen with a pawn comes before
+/// taking it with a rook, which comes before taking a pawn with anything.
+pub fn get_mvv_lva(victim: &PieceType, attacker: &PieceType) -> i32 {
+    (piece_index(victim) * 8 + 5 - piece_index(attacker)) as i32
+}
+
+/// Decides which movements to try first, the sooner a good one is found the more alpha-beta
+/// can cut. The movement from the transposition table goes first, then captures by MVV-LVA,
+/// promotions, the killers of the ply and the rest by history.
+pub struct MoveOrdering {
+    // the last two quiet movements that caused a cutoff at each ply, they often do again in
+    // the positions next to it.
+    killers: Vec<[Option<Movement>; 2]>,
+    // how often each quiet movement caused a cutoff, by color, from and to, deeper ones count
+    // more.
+    history: Vec<[[i32; 64]; 64]>,
+}
+
+impl MoveOrdering {
+    pub fn new() -> MoveOrdering {
+        MoveOrdering {
+            killers: vec![[None, None]; MAX_PLY as usize + 1],
+            history: vec![[[0; 64]; 64]; 2],
+        }
+    }
+
+    /// Sorts `movements` for the side to move, `ply` moves away from the root.
+    pub fn sort(
+        &self,
+        bitboards: &Bitboards,
+        movements: &mut [Movement],
+        hash_move: Option<&Movement>,
+        ply: usize,
+    ) {
+        movements
+            .sort_by_cached_key(|movement| -self.get_score(bitboards, movement, hash_move, ply));
+    }
+
+    fn get_score(
+        &self,
+        bitboards: &Bitboards,
+        movement: &Movement,
+        hash_move: Option<&Movement>,
+        ply: usize,
+    ) -> i32 {
+        if hash_move == Some(movement) {
+            return HASH_MOVE;
+        }
+        let (color, attacker) = match bitboards.get_piece(&movement.from) {
+            None => return 0,
+            Some(piece) => piece,
+        };
+        if is_capture(bitboards, movement) {
+            let victim = match bitboards.get_piece(&movement.to) {
+                Some((_, victim)) => victim,
+                // en passant
+                None => PieceType::Pawn,
+            };
+            return CAPTURE + get_mvv_lva(&victim, &attacker);
+        }
+        if let Some(promotion) = &movement.promotion {
+            return PROMOTION + piece_index(promotion) as i32;
+        }
+        let killers = &self.killers[ply.min(MAX_PLY as usize)];
+        if killers[0].as_ref() == Some(movement) {
+            return FIRST_KILLER;
+        }
+        if killers[1].as_ref() == Some(movement) {
+            return SECOND_KILLER;
+        }
+        self.get_history(&color, movement)
+    }
+
+    pub fn get_history(&self, color: &Color, movement: &Movement) -> i32 {
+        self.history[color_index(color)][square_index(&movement.from)][square_index(&movement.to)]
+    }
+
+    /// A quiet movement caused a cutoff.
+    pub fn add_cutoff(&mut self, color: &Color, movement: &Movement, depth: u32, ply: usize) {
+        let killers = &mut self.killers[ply.min(MAX_PLY as usize)];
+        if killers[0].as_ref() != Some(movement) {
+            killers[1] = killers[0].take();
+            killers[0] = Some(movement.clone());
+        }
+
+        let history = &mut self.history[color_index(color)];
+        let score = &mut history[square_index(&movement.from)][square_index(&movement.to)];
+        *score += (depth * depth) as i32;
+        // halving everything keeps the order while making room.
+        if *score > MAX_HISTORY {
+            for scores in history.iter_mut() {
+                for score in scores.iter_mut() {
+                    *score /= 2;
+                }
+            }
+        }
+    }
+}
+
+impl Default for MoveOrdering {
+    fn default() -> MoveOrdering {
+        MoveOrdering::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::engine::ordering::*;
+    use crate::game::game::Game;
+
+    fn sorted(game: &Game, ordering: &MoveOrdering, hash_move: Option<&str>) -> Vec<String> {
+        let mut movements = game.legal_moves();
+        let hash_move = hash_move.map(|notation| game.parse_coordinate_notation(notation).unwrap());
+        ordering.sort(
+            game.get_board().get_bitboards(),
+            &mut movements,
+            hash_move.as_ref(),
+            0,
+        );
+        movements
+            .iter()
+            .map(|movement| movement.to_string())
+            .collect()
+    }
+
+    #[test]
+    fn most_valuable_victim_least_valuable_attacker() {
+        assert!(
+            get_mvv_lva(&PieceType::Queen, &PieceType::Pawn)
+                > get_mvv_lva(&PieceType::Queen, &PieceType::Rook)
+        );
+        assert!(
+            get_mvv_lva(&PieceType::Queen, &PieceType::King)
+                > get_mvv_lva(&PieceType::Rook, &PieceType::Pawn)
+        );
+    }
+
+    #[test]
+    fn captures_first() {
+        // the pawn on e4 can take the queen on d5 or the knight on f5, the rook on d1 can
+        // take the queen too.
+        let game = Game::from_fen("4k3/8/8/3q1n2/4P3/8/8/3RK3 w - - 0 1").unwrap();
+        let ordering = MoveOrdering::new();
+        let movements = sorted(&game, &ordering, None);
+        assert_eq!(vec!["e4d5", "d1d5", "e4f5"], movements[..3]);
+        // unless the table says otherwise.
+        let movements = sorted(&game, &ordering, Some("e1f2"));
+        assert_eq!(vec!["e1f2", "e4d5", "d1d5", "e4f5"], movements[..4]);
+    }
+
+    #[test]
+    fn killers_then_history() {
+        let game = Game::new();
+        let mut ordering = MoveOrdering::new();
+        let g1f3 = game.parse_coordinate_notation("g1f3").unwrap();
+        let b1c3 = game.parse_coordinate_notation("b1c3").unwrap();
+        let e2e4 = game.parse_coordinate_notation("e2e4").unwrap();
+        // d2d4 cuts off a lot deep down, but not at this ply.
+        let d2d4 = game.parse_coordinate_notation("d2d4").unwrap();
+        ordering.add_cutoff(&Color::White, &d2d4, 10, 5);
+        ordering.add_cutoff(&Color::White, &e2e4, 2, 0);
+        ordering.add_cutoff(&Color::White, &b1c3, 1, 0);
+        ordering.add_cutoff(&Color::White, &g1f3, 1, 0);
+        assert_eq!(
+            vec!["g1f3", "b1c3", "d2d4", "e2e4"],
+            sorted(&game, &ordering, None)[..4]
+        );
+        // the newest killer pushes out the oldest one.
+        assert_eq!([Some(g1f3), Some(b1c3)], ordering.killers[0]);
+        assert_eq!(100, ordering.get_history(&Color::White, &d2d4));
+        assert_eq!(0, ordering.get_history(&Color::Black, &d2d4));
+    }
+
+    #[test]
+    fn history_is_halved_before_it_gets_too_big() {
+        let game = Game::new();
+        let mut ordering = MoveOrdering::new();
+        let e2e4 = game.parse_coordinate_notation("e2e4").unwrap();
+        let d2d4 = game.parse_coordinate_notation("d2d4").unwrap();
+        ordering.add_cutoff(&Color::White, &d2d4, 10, 0);
+        for _ in 0..600 {
+            ordering.add_cutoff(&Color::White, &e2e4, 10, 0);
+        }
+        assert!(ordering.get_history(&Color::White, &e2e4) <= MAX_HISTORY);
+        assert!(ordering.get_history(&Color::White, &d2d4) < 100);
+    }
+}
@@ -0,0 +1,627 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::engine::evaluation::evaluate;
+use crate::engine::ordering::{is_capture, MoveOrdering};
+use crate::engine::time::{Clock, TimeControl, TimeManager};
+use crate::engine::transposition::{Bound, TranspositionTable};
+use crate::game::game::Game;
+use crate::game::movement::Movement;
+use crate::pieces::piece::PieceType;
+
+/// The score of being checkmated right now. Mates further away score a bit less so the engine
+/// goes for the quickest one.
+pub const MATE_SCORE: i32 = 100_000;
+
+// iterative deepening stops here even if there's time left.
+pub const MAX_DEPTH: u32 = 64;
+// how far from the root the search can get, counting the captures at the end.
+pub const MAX_PLY: i32 = MAX_DEPTH as i32 * 2;
+
+/// When the engine should stop thinking, it stops at the first limit reached.
+/// Without any limit it searches up to `MAX_DEPTH`.
+#[derive(Clone, Debug, Default)]
+pub struct SearchLimits {
+    pub depth: Option<u32>,
+    pub time: Option<Duration>,
+    // the engine's clock in a game, the time manager decides how much of it to use.
+    pub time_control: Option<TimeControl>,
+    pub nodes: Option<u64>,
+    // set from another thread to stop early, e.g. when a GUI says so.
+    pub stop: Option<Arc<AtomicBool>>,
+    // where the time is read from, the real time when None.
+    pub clock: Option<Arc<dyn Clock>>,
+}
+
+impl SearchLimits {
+    pub fn depth(depth: u32) -> SearchLimits {
+        SearchLimits {
+            depth: Some(depth),
+            ..SearchLimits::default()
+        }
+    }
+
+    pub fn time(time: Duration) -> SearchLimits {
+        SearchLimits {
+            time: Some(time),
+            ..SearchLimits::default()
+        }
+    }
+
+    pub fn time_control(time_control: TimeControl) -> SearchLimits {
+        SearchLimits {
+            time_control: Some(time_control),
+            ..SearchLimits::default()
+        }
+    }
+}
+
+/// What the engine found, from the last depth it finished.
+#[derive(Clone, Debug)]
+pub struct SearchResult {
+    // None only when there's no legal movement.
+    pub best_move: Option<Movement>,
+    // centipawns from the point of view of the side to move.
+    pub score: i32,
+    pub depth: u32,
+    // every position searched, the ones of the quiescence search included.
+    pub nodes: u64,
+    pub quiescence_nodes: u64,
+    // the movements both sides are expected to play, starting with the best one.
+    pub principal_variation: Vec<Movement>,
+    pub time: Duration,
+    // how full the transposition table is, in permille.
+    pub hashfull: usize,
+}
+
+impl SearchResult {
+    /// How many moves until mate, negative when the side to move is the one getting mated.
+    pub fn get_mate_in(&self) -> Option<i32> {
+        let plies = MATE_SCORE - self.score.abs();
+        if plies > MAX_PLY {
+            return None;
+        }
+        let moves = (plies + 1) / 2;
+        Some(if self.score > 0 { moves } else { -moves })
+    }
+}
+
+struct Searcher<'a> {
+    game: Game,
+    table: &'a mut TranspositionTable,
+    limits: SearchLimits,
+    time: TimeManager,
+    nodes: u64,
+    quiescence_nodes: u64,
+    ordering: MoveOrdering,
+    // set once a limit is reached, whatever is being searched then is thrown away.
+    stopped: bool,
+}
+
+impl<'a> Searcher<'a> {
+    fn new(game: Game, limits: SearchLimits, table: &'a mut TranspositionTable) -> Searcher<'a> {
+        table.new_search();
+        Searcher {
+            game,
+            table,
+            time: TimeManager::new(&limits),
+            limits,
+            nodes: 0,
+            quiescence_nodes: 0,
+            ordering: MoveOrdering::new(),
+            stopped: false,
+        }
+    }
+
+    fn run(&mut self, mut on_depth: impl FnMut(&SearchResult)) -> SearchResult {
+        let mut movements = self.game.legal_moves();
+        let mut result = SearchResult {
+            best_move: movements.first().cloned(),
+            score: 0,
+            depth: 0,
+            nodes: 0,
+            quiescence_nodes: 0,
+            principal_variation: vec![],
+            time: Duration::ZERO,
+            hashfull: 0,
+        };
+        if self.game.is_over() || movements.is_empty() {
+            result.best_move = None;
+            return result;
+        }
+        // captures first until the first depth says which movement is best.
+        self.ordering.sort(
+            self.game.get_board().get_bitboards(),
+            &mut movements,
+            None,
+            0,
+        );
+        let max_depth = self.limits.depth.unwrap_or(MAX_DEPTH).clamp(1, MAX_DEPTH);
+        for depth in 1..=max_depth {
+            let mut principal_variation = vec![];
+            let (score, best) = self.search_root(&movements, depth, &mut principal_variation);
+            if self.stopped {
+                break;
+            }
+            // the best movement goes first next time so cutoffs happen sooner.
+            let best = movements.remove(best);
+            movements.insert(0, best.clone());
+            result.best_move = Some(best);
+            result.score = score;
+            result.depth = depth;
+            result.nodes = self.nodes;
+            result.quiescence_nodes = self.quiescence_nodes;
+            result.principal_variation = principal_variation;
+            result.time = self.time.elapsed();
+            result.hashfull = self.table.get_hashfull();
+            on_depth(&result);
+            // no point looking deeper once a mate was found.
+            if score.abs() >= MATE_SCORE - depth as i32 {
+                break;
+            }
+            self.time.on_depth(score, &movements[0]);
+            if !self.time.should_deepen() {
+                break;
+            }
+        }
+        result.nodes = self.nodes;
+        result.quiescence_nodes = self.quiescence_nodes;
+        result.time = self.time.elapsed();
+        result
+    }
+
+    // returns the score and the index of the best movement.
+    fn search_root(
+        &mut self,
+        movements: &[Movement],
+        depth: u32,
+        principal_variation: &mut Vec<Movement>,
+    ) -> (i32, usize) {
+        let mut alpha = -MATE_SCORE - 1;
+        let beta = MATE_SCORE + 1;
+        let mut best = 0;
+        let mut line = vec![];
+        for (i, movement) in movements.iter().enumerate() {
+            self.game.make_legal_movement(movement);
+            let score = -self.negamax(depth - 1, 1, -beta, -alpha, &mut line);
+            self.game.undo().expect("the movement was just played");
+            if self.stopped {
+                break;
+            }
+            if score > alpha {
+                alpha = score;
+                best = i;
+                set_principal_variation(principal_variation, movement, &line);
+            }
+        }
+        (alpha, best)
+    }
+
+    // `principal_variation` ends up with the best line from here when the score is exact.
+    fn negamax(
+        &mut self,
+        depth: u32,
+        ply: i32,
+        mut alpha: i32,
+        beta: i32,
+        principal_variation: &mut Vec<Movement>,
+    ) -> i32 {
+        principal_variation.clear();
+        if depth == 0 {
+            return self.quiescence(ply, alpha, beta);
+        }
+        self.nodes += 1;
+        if self.should_stop() {
+            self.stopped = true;
+            return 0;
+        }
+        if self.is_draw() {
+            return 0;
+        }
+        let movements = self.game.legal_moves();
+        if movements.is_empty() {
+            if self
+                .game
+                .get_board()
+                .is_king_in_check(self.game.get_current_color())
+            {
+                return -MATE_SCORE + ply;
+            }
+            return 0;
+        }
+
+        let key = self.game.get_hash();
+        let mut hash_move = None;
+        if let Some(entry) = self.table.probe(key) {
+            hash_move = entry.get_best_move().cloned();
+            if entry.get_depth() >= depth {
+                let score = entry.get_score(ply);
+                let usable = match entry.get_bound() {
+                    Bound::Exact => true,
+                    Bound::Lower => score >= beta,
+                    Bound::Upper => score <= alpha,
+                };
+                if usable {
+                    principal_variation.extend(entry.get_best_move().cloned());
+                    return score.clamp(alpha, beta);
+                }
+            }
+        }
+
+        let mut movements = movements;
+        self.ordering.sort(
+            self.game.get_board().get_bitboards(),
+            &mut movements,
+            hash_move.as_ref(),
+            ply as usize,
+        );
+        let original_alpha = alpha;
+        let mut best_move = None;
+        let mut line = vec![];
+        for movement in &movements {
+            let quiet = !is_capture(self.game.get_board().get_bitboards(), movement)
+                && movement.promotion.is_none();
+            self.game.make_legal_movement(movement);
+            let score = -self.negamax(depth - 1, ply + 1, -beta, -alpha, &mut line);
+            self.game.undo().expect("the movement was just played");
+            if self.stopped {
+                return 0;
+            }
+            if score >= beta {
+                if quiet {
+                    let color = self.game.get_current_color().clone();
+                    self.ordering
+                        .add_cutoff(&color, movement, depth, ply as usize);
+                }
+                self.table
+                    .store(key, depth, Bound::Lower, beta, ply, Some(movement.clone()));
+                return beta;
+            }
+            if score > alpha {
+                alpha = score;
+                best_move = Some(movement.clone());
+                set_principal_variation(principal_variation, movement, &line);
+            }
+        }
+        let bound = match alpha > original_alpha {
+            true => Bound::Exact,
+            false => Bound::Upper,
+        };
+        self.table.store(key, depth, bound, alpha, ply, best_move);
+        alpha
+    }
+
+    /// Once the depth runs out only captures and promotions are played, so the position is
+    /// never judged in the middle of an exchange. The side to move can always stop capturing,
+    /// unless it's in check, then every movement is tried.
+    fn quiescence(&mut self, ply: i32, mut alpha: i32, beta: i32) -> i32 {
+        self.nodes += 1;
+        self.quiescence_nodes += 1;
+        if self.should_stop() {
+            self.stopped = true;
+            return 0;
+        }
+        if self.is_draw() {
+            return 0;
+        }
+        let mut movements = self.game.legal_moves();
+        let board = self.game.get_board();
+        let color = self.game.get_current_color();
+        let in_check = board.is_king_in_check(color);
+        if movements.is_empty() {
+            return if in_check { -MATE_SCORE + ply } else { 0 };
+        }
+        if ply >= MAX_PLY {
+            return evaluate(board, color);
+        }
+        if !in_check {
+            let stand_pat = evaluate(board, color);
+            if stand_pat >= beta {
+                return beta;
+            }
+            alpha = alpha.max(stand_pat);
+            movements = self.get_good_captures(movements);
+        }
+        for movement in &movements {
+            self.game.make_legal_movement(movement);
+            let score = -self.quiescence(ply + 1, -beta, -alpha);
+            self.game.undo().expect("the movement was just played");
+            if self.stopped {
+                return 0;
+            }
+            if score >= beta {
+                return beta;
+            }
+            alpha = alpha.max(score);
+        }
+        alpha
+    }
+
+    // the captures that don't lose material and the queen promotions, the ones winning the
+    // most first.
+    fn get_good_captures(&self, movements: Vec<Movement>) -> Vec<Movement> {
+        let board = self.game.get_board();
+        let mut captures: Vec<(i32, Movement)> = movements
+            .into_iter()
+            .filter_map(|movement| {
+                let is_capture = is_capture(board.get_bitboards(), &movement);
+                let mut exchange = match is_capture {
+                    true => board.get_static_exchange(&movement.from, &movement.to),
+                    false => 0,
+                };
+                match &movement.promotion {
+                    Some(PieceType::Queen) => {
+                        exchange += PieceType::Queen.get_value() - PieceType::Pawn.get_value()
+                    }
+                    Some(_) => return None,
+                    None if !is_capture || exchange < 0 => return None,
+                    None => {}
+                }
+                Some((exchange, movement))
+            })
+            .collect();
+        captures.sort_by_key(|(exchange, _)| -exchange);
+        captures.into_iter().map(|(_, movement)| movement).collect()
+    }
+
+    // a position that already happened is a draw too, if it's good for one side they can
+    // repeat it again.
+    fn is_draw(&self) -> bool {
+        self.game.get_halfmove_clock() >= 100
+            || self.game.get_repetition_count() >= 2
+            || self.game.get_board().has_insufficient_material()
+    }
+
+    fn should_stop(&self) -> bool {
+        if let Some(nodes) = self.limits.nodes {
+            if self.nodes > nodes {
+                return true;
+            }
+        }
+        // checking the clock on every node is slow.
+        if self.nodes.is_multiple_of(1024) {
+            if let Some(stop) = &self.limits.stop {
+                if stop.load(Ordering::Relaxed) {
+                    return true;
+                }
+            }
+            return self.time.is_out_of_time();
+        }
+        false
+    }
+}
+
+fn set_principal_variation(
+    principal_variation: &mut Vec<Movement>,
+    movement: &Movement,
+    line: &[Movement],
+) {
+    principal_variation.clear();
+    principal_variation.push(movement.clone());
+    principal_variation.extend_from_slice(line);
+}
+
+/// Looking for the best movement with a negamax alpha-beta search, deepening one ply at a time
+/// until a limit is reached.
+impl Game {
+    /// The movement the engine would play, None when the game is over.
+    pub fn best_move(&self, limits: &SearchLimits) -> Option<Movement> {
+        self.search(limits).best_move
+    }
+
+    /// Same as `best_move` but with the score and how far it looked.
+    pub fn search(&self, limits: &SearchLimits) -> SearchResult {
+        self.search_with_info(limits, &mut TranspositionTable::default(), |_| {})
+    }
+
+    /// Same as `search` but keeping what it learns in `table` for the next searches, and
+    /// calling `on_depth` with the result so far every time a depth is done.
+    pub fn search_with_info(
+        &self,
+        limits: &SearchLimits,
+        table: &mut TranspositionTable,
+        on_depth: impl FnMut(&SearchResult),
+    ) -> SearchResult {
+        Searcher::new(self.clone(), limits.clone(), table).run(on_depth)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use crate::engine::search::*;
+    use crate::engine::time::MockClock;
+    use crate::game::game_result::{EndReason, GameResult};
+    use crate::pieces::piece::PieceType;
+
+    fn best_move(fen: &str, depth: u32) -> String {
+        let game = Game::from_fen(fen).unwrap();
+        game.best_move(&SearchLimits::depth(depth))
+            .unwrap()
+            .to_string()
+    }
+
+    #[test]
+    fn finds_mate_in_one() {
+        // back rank mate
+        assert_eq!("a1a8", best_move("6k1/5ppp/8/8/8/8/8/R5K1 w - - 0 1", 3));
+        let game = Game::from_fen("6k1/5ppp/8/8/8/8/8/R5K1 w - - 0 1").unwrap();
+        let result = game.search(&SearchLimits::depth(3));
+        assert_eq!(MATE_SCORE - 1, result.score);
+        assert_eq!(Some(1), result.get_mate_in());
+    }
+
+    #[test]
+    fn finds_mate_in_two() {
+        // 1. Kb6 Kb8 2. Rh8#
+        let game = Game::from_fen("k7/8/2K5/8/8/8/8/7R w - - 0 1").unwrap();
+        let result = game.search(&SearchLimits::depth(4));
+        assert_eq!(Some(2), result.get_mate_in());
+        let result = game.search(&SearchLimits::depth(2));
+        assert_eq!(None, result.get_mate_in());
+    }
+
+    #[test]
+    fn takes_the_hanging_queen() {
+        assert_eq!("e4d5", best_move("4k3/8/8/3q4/4P3/8/8/4K3 w - - 0 1", 2));
+    }
+
+    #[test]
+    fn doesnt_take_a_defended_pawn_with_the_queen() {
+        let movement = best_move("4k3/2p5/3p4/8/8/8/3Q4/4K3 w - - 0 1", 2);
+        assert_ne!("d2d6", movement);
+    }
+
+    #[test]
+    fn sees_the_recapture_past_the_depth() {
+        // Qxd6 looks like a free pawn one move ahead, until cxd6.
+        assert_ne!("d2d6", best_move("4k3/2p5/3p4/8/8/8/3Q4/4K3 w - - 0 1", 1));
+        // Nxe5 Nxe5 Rxe5 Bxe5 loses the knight for a pawn.
+        let fen = "1k1r3q/1ppn3p/p4b2/4p3/8/P2N2P1/1PP1R1BP/2K1Q3 w - - 0 1";
+        assert_ne!("d3e5", best_move(fen, 1));
+        let game = Game::from_fen(fen).unwrap();
+        let result = game.search(&SearchLimits::depth(1));
+        assert!(result.score > -100, "{}", result.score);
+    }
+
+    #[test]
+    fn promotes_to_a_queen() {
+        let game = Game::from_fen("8/4P1k1/8/8/8/8/8/4K3 w - - 0 1").unwrap();
+        let movement = game.best_move(&SearchLimits::depth(2)).unwrap();
+        assert_eq!(Some(PieceType::Queen), movement.promotion);
+    }
+
+    #[test]
+    fn avoids_stalemate_when_winning() {
+        // Qc7 stalemates, Qc8 is mate
+        let mut game = Game::from_fen("k7/8/1K6/8/8/8/8/2Q5 w - - 0 1").unwrap();
+        let movement = game.best_move(&SearchLimits::depth(2)).unwrap();
+        game.play_movement(&movement).unwrap();
+        assert_eq!(
+            Some(&GameResult::WhiteWins(EndReason::Checkmate)),
+            game.get_result()
+        );
+    }
+
+    #[test]
+    fn no_move_when_the_game_is_over() {
+        // fool's mate
+        let game = Game::from_fen("rnb1kbnr/pppp1ppp/8/4p3/6Pq/5P2/PPPPP2P/RNBQKBNR w KQkq - 1 3")
+            .unwrap();
+        assert_eq!(None, game.best_move(&SearchLimits::depth(3)));
+    }
+
+    #[test]
+    fn time_manager_decides_how_long_to_think() {
+        let game = Game::new();
+        let time_control = TimeControl {
+            time_left: Duration::from_secs(60),
+            ..TimeControl::default()
+        };
+        // a clock that never moves lets it go as deep as it's told.
+        let limits = SearchLimits {
+            depth: Some(3),
+            clock: Some(Arc::new(MockClock::new())),
+            ..SearchLimits::time_control(time_control.clone())
+        };
+        assert_eq!(3, game.search(&limits).depth);
+
+        // 10ms every 1024 nodes, a few hundred thousand nodes are all it has time for.
+        let limits = SearchLimits {
+            clock: Some(Arc::new(MockClock::ticking(Duration::from_millis(10)))),
+            ..SearchLimits::time_control(TimeControl {
+                time_left: Duration::from_secs(10),
+                ..time_control
+            })
+        };
+        let result = game.search(&limits);
+        let (_, hard) = limits.time_control.unwrap().get_limits();
+        assert!(result.depth >= 1);
+        assert!(
+            result.time <= hard + Duration::from_millis(20),
+            "{:?}",
+            result.time
+        );
+        assert!(result.best_move.is_some());
+    }
+
+    #[test]
+    fn stops_when_the_time_is_up() {
+        let game = Game::new();
+        let result = game.search(&SearchLimits::time(Duration::from_millis(50)));
+        assert!(result.best_move.is_some());
+        assert!(result.depth >= 1);
+        assert!(result.depth < MAX_DEPTH);
+    }
+
+    #[test]
+    fn principal_variation_leads_to_the_mate() {
+        let game = Game::from_fen("k7/8/2K5/8/8/8/8/7R w - - 0 1").unwrap();
+        let mut depths = vec![];
+        let mut table = TranspositionTable::default();
+        let result = game.search_with_info(&SearchLimits::depth(4), &mut table, |result| {
+            depths.push(result.depth)
+        });
+        assert_eq!(vec![1, 2, 3], depths);
+        assert_eq!(3, result.principal_variation.len());
+        assert_eq!(
+            result.best_move.as_ref(),
+            result.principal_variation.first()
+        );
+        let mut after = game.clone();
+        for movement in &result.principal_variation {
+            after.play_movement(movement).unwrap();
+        }
+        assert_eq!(
+            Some(&GameResult::WhiteWins(EndReason::Checkmate)),
+            after.get_result()
+        );
+    }
+
+    #[test]
+    fn transposition_table_saves_work() {
+        let game =
+            Game::from_fen("r1bqkbnr/pppp1ppp/2n5/4p3/4P3/5N2/PPPP1PPP/RNBQKB1R w KQkq - 2 3")
+                .unwrap();
+        let limits = SearchLimits::depth(3);
+        let mut table = TranspositionTable::new(1);
+        let first = game.search_with_info(&limits, &mut table, |_| {});
+        let second = game.search_with_info(&limits, &mut table, |_| {});
+        // the second time around the earlier depths come straight from the table.
+        assert!(
+            second.nodes < first.nodes,
+            "{} {}",
+            first.nodes,
+            second.nodes
+        );
+        assert_eq!(first.score, second.score);
+        assert_eq!(first.best_move, second.best_move);
+    }
+
+    #[test]
+    fn stops_when_told_to() {
+        let stop = Arc::new(AtomicBool::new(true));
+        let limits = SearchLimits {
+            stop: Some(stop),
+            ..SearchLimits::default()
+        };
+        // even stopped right away there is a movement to play.
+        let result = Game::new().search(&limits);
+        assert!(result.best_move.is_some());
+        assert!(result.depth < MAX_DEPTH);
+    }
+
+    #[test]
+    fn stops_after_the_nodes() {
+        let game = Game::new();
+        let limits = SearchLimits {
+            nodes: Some(1000),
+            ..SearchLimits::default()
+        };
+        let result = game.search(&limits);
+        assert!(result.best_move.is_some());
+        assert!(result.nodes <= 1001);
+    }
+}
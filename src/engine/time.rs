@@ -0,0 +1,358 @@
+use std::fmt::Debug;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use crate::engine::search::SearchLimits;
+use crate::game::movement::Movement;
+
+// when only the clock is given, assume the game lasts this many more moves.
+const MOVES_TO_GO: u32 = 30;
+// left on the clock for the GUI to read the move and press the clock.
+const MOVE_OVERHEAD: Duration = Duration::from_millis(50);
+// the hard limit is this many times the soft one...
+const HARD_LIMIT_FACTOR: u32 = 4;
+// ...but never more than this share of the clock, in percent, so one move can't lose the game.
+const MAX_CLOCK_SHARE: u32 = 75;
+// percent of the soft limit added when the best movement changes, halved every depth after.
+const BEST_MOVE_CHANGE_EXTENSION: u32 = 50;
+// percent of the soft limit added when the score drops by more than `FAIL_LOW_MARGIN`.
+const FAIL_LOW_EXTENSION: u32 = 50;
+const FAIL_LOW_MARGIN: i32 = 30;
+
+/// Where the time manager reads the time from. Tests use a `MockClock` so they don't depend on
+/// how fast the machine running them is.
+pub trait Clock: Debug + Send + Sync {
+    /// The time since some fixed point, only the difference between two calls matters.
+    fn now(&self) -> Duration;
+}
+
+/// The real time.
+#[derive(Debug)]
+pub struct WallClock {
+    start: Instant,
+}
+
+impl WallClock {
+    pub fn new() -> WallClock {
+        WallClock {
+            start: Instant::now(),
+        }
+    }
+}
+
+impl Default for WallClock {
+    fn default() -> WallClock {
+        WallClock::new()
+    }
+}
+
+impl Clock for WallClock {
+    fn now(&self) -> Duration {
+        self.start.elapsed()
+    }
+}
+
+/// A clock that only moves when told to, or by `step` every time it's read.
+#[derive(Debug, Default)]
+pub struct MockClock {
+    // nanoseconds.
+    now: AtomicU64,
+    step: Duration,
+}
+
+impl MockClock {
+    pub fn new() -> MockClock {
+        MockClock::default()
+    }
+
+    /// Moves `step` forward every time it's read, e.g. every 1024 nodes of a search.
+    pub fn ticking(step: Duration) -> MockClock {
+        MockClock {
+            now: AtomicU64::new(0),
+            step,
+        }
+    }
+
+    pub fn advance(&self, time: Duration) {
+        self.now
+            .fetch_add(time.as_nanos() as u64, Ordering::Relaxed);
+    }
+}
+
+impl Clock for MockClock {
+    fn now(&self) -> Duration {
+        let now = self
+            .now
+            .fetch_add(self.step.as_nanos() as u64, Ordering::Relaxed);
+        Duration::from_nanos(now)
+    }
+}
+
+/// What's left on the engine's clock, as the GUI tells it.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct TimeControl {
+    pub time_left: Duration,
+    // added after every move.
+    pub increment: Duration,
+    // until the next time control, None when what's left is for the rest of the game.
+    pub moves_to_go: Option<u32>,
+}
+
+impl TimeControl {
+    /// How long to think when things go as expected and the most it can ever take.
+    pub fn get_limits(&self) -> (Duration, Duration) {
+        let available = self.time_left.saturating_sub(MOVE_OVERHEAD);
+        let moves_to_go = self.moves_to_go.unwrap_or(MOVES_TO_GO).max(1);
+        let soft = available / moves_to_go + self.increment * 3 / 4;
+        let hard = (soft * HARD_LIMIT_FACTOR).min(available * MAX_CLOCK_SHARE / 100);
+        // in whole milliseconds, like the GUIs count.
+        let millis = |time: Duration| Duration::from_millis(time.as_millis().max(1) as u64);
+        (millis(soft.min(hard)), millis(hard))
+    }
+}
+
+/// Decides when the search stops. After every depth it's asked whether to go one deeper, which
+/// it allows until the soft limit, stretched when the search is unsure: the best movement
+/// changed or the score dropped. In the middle of a depth only the hard limit stops it.
+pub struct TimeManager {
+    clock: Arc<dyn Clock>,
+    start: Duration,
+    soft: Option<Duration>,
+    hard: Option<Duration>,
+    // percent added to the soft limit because the best movement keeps changing.
+    instability: u32,
+    // percent added to the soft limit because the score dropped on the last depth.
+    fail_low: u32,
+    // the score and best movement of the last depth.
+    previous: Option<(i32, Movement)>,
+}
+
+impl TimeManager {
+    /// Starts counting now, using the clock of `limits` or the real one.
+    pub fn new(limits: &SearchLimits) -> TimeManager {
+        let clock = match &limits.clock {
+            Some(clock) => clock.clone(),
+            None => Arc::new(WallClock::new()),
+        };
+        let (mut soft, mut hard) = match &limits.time_control {
+            Some(time_control) => {
+                let (soft, hard) = time_control.get_limits();
+                (Some(soft), Some(hard))
+            }
+            None => (None, None),
+        };
+        // a fixed time per move is both limits, whichever is shorter.
+        if let Some(time) = limits.time {
+            soft = Some(soft.map_or(time, |soft| soft.min(time)));
+            hard = Some(hard.map_or(time, |hard| hard.min(time)));
+        }
+        TimeManager {
+            start: clock.now(),
+            clock,
+            soft,
+            hard,
+            instability: 0,
+            fail_low: 0,
+            previous: None,
+        }
+    }
+
+    pub fn elapsed(&self) -> Duration {
+        self.clock.now().saturating_sub(self.start)
+    }
+
+    /// The soft limit with the extensions, never past the hard one.
+    pub fn get_soft_limit(&self) -> Option<Duration> {
+        let extension = 100 + self.instability + self.fail_low;
+        let soft = self.soft? * extension / 100;
+        Some(match self.hard {
+            Some(hard) => soft.min(hard),
+            None => soft,
+        })
+    }
+
+    pub fn get_hard_limit(&self) -> Option<Duration> {
+        self.hard
+    }
+
+    /// Called after every depth with its score and best movement.
+    pub fn on_depth(&mut self, score: i32, best_move: &Movement) {
+        self.instability /= 2;
+        self.fail_low = 0;
+        if let Some((previous_score, previous_move)) = &self.previous {
+            if previous_move != best_move {
+                self.instability += BEST_MOVE_CHANGE_EXTENSION;
+            }
+            if score < previous_score - FAIL_LOW_MARGIN {
+                self.fail_low = FAIL_LOW_EXTENSION;
+            }
+        }
+        self.previous = Some((score, best_move.clone()));
+    }
+
+    /// Whether there's time to start another depth.
+    pub fn should_deepen(&self) -> bool {
+        match self.get_soft_limit() {
+            Some(soft) => self.elapsed() < soft,
+            None => true,
+        }
+    }
+
+    /// Whether the search has to stop right away.
+    pub fn is_out_of_time(&self) -> bool {
+        match self.hard {
+            Some(hard) => self.elapsed() >= hard,
+            None => false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::engine::time::*;
+    use crate::pieces::validator::row_column::RowColumn;
+
+    fn second() -> Duration {
+        Duration::from_secs(1)
+    }
+
+    fn with_clock(time_control: TimeControl, clock: &Arc<MockClock>) -> SearchLimits {
+        SearchLimits {
+            time_control: Some(time_control),
+            clock: Some(clock.clone()),
+            ..SearchLimits::default()
+        }
+    }
+
+    fn movement(column: u8) -> Movement {
+        Movement::new(RowColumn::new(6, column), RowColumn::new(4, column))
+    }
+
+    #[test]
+    fn limits_from_the_clock() {
+        let time_control = TimeControl {
+            time_left: 60 * second(),
+            ..TimeControl::default()
+        };
+        // a thirtieth of the clock, minus what the GUI needs.
+        assert_eq!(
+            (Duration::from_millis(1998), Duration::from_millis(7993)),
+            time_control.get_limits()
+        );
+        let time_control = TimeControl {
+            time_left: 60 * second(),
+            increment: second(),
+            moves_to_go: Some(10),
+        };
+        assert_eq!(
+            (Duration::from_millis(6745), Duration::from_millis(26980)),
+            time_control.get_limits()
+        );
+    }
+
+    #[test]
+    fn never_flags() {
+        // a big increment doesn't help when the clock is almost out.
+        let time_control = TimeControl {
+            time_left: second(),
+            increment: 10 * second(),
+            moves_to_go: Some(1),
+        };
+        let (soft, hard) = time_control.get_limits();
+        assert_eq!(Duration::from_millis(712), hard);
+        assert_eq!(hard, soft);
+        let time_control = TimeControl::default();
+        assert_eq!(
+            (Duration::from_millis(1), Duration::from_millis(1)),
+            time_control.get_limits()
+        );
+    }
+
+    #[test]
+    fn fixed_time_per_move() {
+        let limits = SearchLimits {
+            clock: Some(Arc::new(MockClock::new())),
+            ..SearchLimits::time(3 * second())
+        };
+        let mut manager = TimeManager::new(&limits);
+        // no extension past it.
+        manager.on_depth(0, &movement(4));
+        manager.on_depth(-100, &movement(3));
+        assert_eq!(Some(3 * second()), manager.get_soft_limit());
+        assert_eq!(Some(3 * second()), manager.get_hard_limit());
+        // or to what the clock allows, if that's less.
+        let limits = SearchLimits {
+            time: Some(3 * second()),
+            ..with_clock(
+                TimeControl {
+                    time_left: 2 * second(),
+                    increment: Duration::ZERO,
+                    moves_to_go: Some(1),
+                },
+                &Arc::new(MockClock::new()),
+            )
+        };
+        assert_eq!(
+            Some(Duration::from_millis(1462)),
+            TimeManager::new(&limits).get_hard_limit()
+        );
+    }
+
+    #[test]
+    fn soft_and_hard_limits() {
+        let clock = Arc::new(MockClock::new());
+        clock.advance(10 * second());
+        let time_control = TimeControl {
+            time_left: 60 * second(),
+            ..TimeControl::default()
+        };
+        let manager = TimeManager::new(&with_clock(time_control, &clock));
+        assert_eq!(Duration::ZERO, manager.elapsed());
+        clock.advance(second());
+        assert_eq!(second(), manager.elapsed());
+        assert_eq!(true, manager.should_deepen());
+        clock.advance(second());
+        assert_eq!(false, manager.should_deepen());
+        assert_eq!(false, manager.is_out_of_time());
+        clock.advance(6 * second());
+        assert_eq!(true, manager.is_out_of_time());
+    }
+
+    #[test]
+    fn extends_when_unsure() {
+        let clock = Arc::new(MockClock::new());
+        let time_control = TimeControl {
+            time_left: 60 * second(),
+            ..TimeControl::default()
+        };
+        let mut manager = TimeManager::new(&with_clock(time_control, &clock));
+        let soft = Duration::from_millis(1998);
+        manager.on_depth(20, &movement(4));
+        assert_eq!(Some(soft), manager.get_soft_limit());
+        manager.on_depth(25, &movement(4));
+        assert_eq!(Some(soft), manager.get_soft_limit());
+
+        // the best movement changed.
+        manager.on_depth(25, &movement(3));
+        assert_eq!(Some(soft * 3 / 2), manager.get_soft_limit());
+        // and stayed, the extension fades.
+        manager.on_depth(25, &movement(3));
+        assert_eq!(Some(soft * 5 / 4), manager.get_soft_limit());
+
+        // the score dropped.
+        manager.on_depth(-50, &movement(3));
+        assert_eq!(Some(soft * 162 / 100), manager.get_soft_limit());
+        manager.on_depth(-50, &movement(3));
+        assert_eq!(Some(soft * 106 / 100), manager.get_soft_limit());
+    }
+
+    #[test]
+    fn ticking_clock() {
+        let clock = MockClock::ticking(Duration::from_millis(10));
+        assert_eq!(Duration::ZERO, clock.now());
+        assert_eq!(Duration::from_millis(10), clock.now());
+        clock.advance(second());
+        assert_eq!(Duration::from_millis(1020), clock.now());
+    }
+}
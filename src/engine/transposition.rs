@@ -0,0 +1,259 @@
+use std::mem;
+
+use crate::engine::search::{MATE_SCORE, MAX_PLY};
+use crate::game::movement::Movement;
+
+// megabytes used when nothing else is asked for.
+pub const DEFAULT_HASH_SIZE: usize = 16;
+pub const MAX_HASH_SIZE: usize = 4096;
+
+/// What a stored score says about the real one. A search that was cut off only knows the score
+/// is at least `beta`, one where nothing beat `alpha` only knows it's at most `alpha`.
+#[derive(Clone, Copy, Eq, PartialEq, Debug)]
+pub enum Bound {
+    Exact,
+    Lower,
+    Upper,
+}
+
+#[derive(Clone, Debug)]
+pub struct Entry {
+    // the whole Zobrist key, to tell apart the positions sharing a slot.
+    key: u64,
+    depth: u32,
+    bound: Bound,
+    // mates are counted from this position, not from the root.
+    score: i32,
+    best_move: Option<Movement>,
+    // the search that stored it, older entries get replaced first.
+    generation: u8,
+}
+
+impl Entry {
+    pub fn get_depth(&self) -> u32 {
+        self.depth
+    }
+
+    pub fn get_bound(&self) -> Bound {
+        self.bound
+    }
+
+    /// The score seen from the root, `ply` moves away from where it's stored.
+    pub fn get_score(&self, ply: i32) -> i32 {
+        from_table_score(self.score, ply)
+    }
+
+    pub fn get_best_move(&self) -> Option<&Movement> {
+        self.best_move.as_ref()
+    }
+}
+
+/// Remembers what the search found for each position, by Zobrist key, so positions reached
+/// through different move orders are only searched once.
+/// It has a fixed number of slots, when two positions want the same one the deeper or newer
+/// search wins.
+pub struct TranspositionTable {
+    entries: Vec<Option<Entry>>,
+    generation: u8,
+}
+
+impl TranspositionTable {
+    /// A table taking about `megabytes` of memory.
+    pub fn new(megabytes: usize) -> TranspositionTable {
+        let megabytes = megabytes.clamp(1, MAX_HASH_SIZE);
+        let size = megabytes * 1024 * 1024 / mem::size_of::<Option<Entry>>();
+        TranspositionTable {
+            entries: vec![None; size],
+            generation: 0,
+        }
+    }
+
+    pub fn get_size(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Forgets everything, e.g. for a new game.
+    pub fn clear(&mut self) {
+        self.entries.fill(None);
+        self.generation = 0;
+    }
+
+    /// Called before every search so what older ones stored can be replaced.
+    pub fn new_search(&mut self) {
+        self.generation = self.generation.wrapping_add(1);
+    }
+
+    fn get_index(&self, key: u64) -> usize {
+        (key % self.entries.len() as u64) as usize
+    }
+
+    pub fn probe(&self, key: u64) -> Option<&Entry> {
+        self.entries[self.get_index(key)]
+            .as_ref()
+            .filter(|entry| entry.key == key)
+    }
+
+    /// `score` is from the root, `ply` moves away.
+    pub fn store(
+        &mut self,
+        key: u64,
+        depth: u32,
+        bound: Bound,
+        score: i32,
+        ply: i32,
+        best_move: Option<Movement>,
+    ) {
+        let index = self.get_index(key);
+        let generation = self.generation;
+        if let Some(entry) = &self.entries[index] {
+            // a deeper search of the same position is worth more even if it's older.
+            let replace = match entry.key == key {
+                true => depth >= entry.depth,
+                false => entry.generation != generation || depth >= entry.depth,
+            };
+            if !replace {
+                return;
+            }
+        }
+        // keep the move we had if this search didn't find one.
+        let best_move = match (&self.entries[index], best_move) {
+            (_, Some(best_move)) => Some(best_move),
+            (Some(entry), None) if entry.key == key => entry.best_move.clone(),
+            _ => None,
+        };
+        self.entries[index] = Some(Entry {
+            key,
+            depth,
+            bound,
+            score: to_table_score(score, ply),
+            best_move,
+            generation,
+        });
+    }
+
+    /// How full the table is in permille, looking at the first thousand slots like UCI does.
+    pub fn get_hashfull(&self) -> usize {
+        let sample = self.entries.len().min(1000);
+        let used = self.entries[..sample]
+            .iter()
+            .filter(|entry| {
+                entry
+                    .as_ref()
+                    .is_some_and(|entry| entry.generation == self.generation)
+            })
+            .count();
+        used * 1000 / sample.max(1)
+    }
+}
+
+impl Default for TranspositionTable {
+    fn default() -> TranspositionTable {
+        TranspositionTable::new(DEFAULT_HASH_SIZE)
+    }
+}
+
+fn is_mate_score(score: i32) -> bool {
+    score.abs() >= MATE_SCORE - MAX_PLY
+}
+
+// a mate in 3 from the root is a mate in 1 two plies later, so stored mates count from the
+// position itself.
+fn to_table_score(score: i32, ply: i32) -> i32 {
+    match score {
+        score if !is_mate_score(score) => score,
+        score if score > 0 => score + ply,
+        score => score - ply,
+    }
+}
+
+fn from_table_score(score: i32, ply: i32) -> i32 {
+    match score {
+        score if !is_mate_score(score) => score,
+        score if score > 0 => score - ply,
+        score => score + ply,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::engine::transposition::*;
+    use crate::pieces::validator::row_column::RowColumn;
+
+    fn e2e4() -> Movement {
+        Movement::new(RowColumn::new(6, 4), RowColumn::new(4, 4))
+    }
+
+    #[test]
+    fn size_follows_the_megabytes() {
+        let small = TranspositionTable::new(1);
+        let big = TranspositionTable::new(4);
+        assert!(small.get_size() > 1000);
+        assert_eq!(4, big.get_size() / small.get_size());
+        assert_eq!(small.get_size(), TranspositionTable::new(0).get_size());
+    }
+
+    #[test]
+    fn store_and_probe() {
+        let mut table = TranspositionTable::new(1);
+        assert_eq!(true, table.probe(42).is_none());
+        table.store(42, 3, Bound::Lower, 25, 2, Some(e2e4()));
+        let entry = table.probe(42).unwrap();
+        assert_eq!(3, entry.get_depth());
+        assert_eq!(Bound::Lower, entry.get_bound());
+        assert_eq!(25, entry.get_score(5));
+        assert_eq!(Some(&e2e4()), entry.get_best_move());
+        // same slot, different position.
+        let other = 42 + table.get_size() as u64;
+        assert_eq!(true, table.probe(other).is_none());
+
+        // storing again without a move keeps the old one.
+        table.store(42, 4, Bound::Upper, 10, 2, None);
+        assert_eq!(Some(&e2e4()), table.probe(42).unwrap().get_best_move());
+        // but a shallower search of the same position doesn't replace it.
+        table.new_search();
+        table.store(42, 1, Bound::Exact, 30, 2, None);
+        assert_eq!(4, table.probe(42).unwrap().get_depth());
+
+        table.clear();
+        assert_eq!(true, table.probe(42).is_none());
+    }
+
+    #[test]
+    fn mate_scores_count_from_the_position() {
+        let mut table = TranspositionTable::new(1);
+        // found 4 plies from the root, mate 3 plies after that.
+        table.store(7, 3, Bound::Exact, MATE_SCORE - 7, 4, None);
+        // reached 2 plies from the root in another search it's mate in 5 plies.
+        assert_eq!(MATE_SCORE - 5, table.probe(7).unwrap().get_score(2));
+        table.store(7, 3, Bound::Exact, -MATE_SCORE + 7, 4, None);
+        assert_eq!(-MATE_SCORE + 5, table.probe(7).unwrap().get_score(2));
+    }
+
+    #[test]
+    fn deeper_and_newer_entries_win() {
+        let mut table = TranspositionTable::new(1);
+        let other = 42 + table.get_size() as u64;
+        table.store(42, 5, Bound::Exact, 0, 0, None);
+        // shallower searches don't replace deeper ones of the same search.
+        table.store(other, 2, Bound::Exact, 0, 0, None);
+        assert_eq!(true, table.probe(42).is_some());
+        table.store(other, 6, Bound::Exact, 0, 0, None);
+        assert_eq!(true, table.probe(other).is_some());
+        // anything replaces what older searches left.
+        table.new_search();
+        table.store(42, 1, Bound::Exact, 0, 0, None);
+        assert_eq!(true, table.probe(42).is_some());
+    }
+
+    #[test]
+    fn hashfull() {
+        let mut table = TranspositionTable::new(1);
+        assert_eq!(0, table.get_hashfull());
+        for key in 0..100 {
+            table.store(key, 1, Bound::Exact, 0, 0, None);
+        }
+        assert_eq!(100, table.get_hashfull());
+        table.new_search();
+        assert_eq!(0, table.get_hashfull());
+    }
+}
@@ -0,0 +1,436 @@
+use std::io::{BufRead, Write};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
+use crate::engine::bench::bench;
+use crate::engine::search::{SearchLimits, SearchResult};
+use crate::engine::time::TimeControl;
+use crate::engine::transposition::{TranspositionTable, DEFAULT_HASH_SIZE, MAX_HASH_SIZE};
+use crate::game::game::Game;
+use crate::pieces::color::Color;
+
+pub const ENGINE_NAME: &str = "Chess";
+pub const ENGINE_AUTHOR: &str = "the chess developers";
+
+// how deep `bench` searches when not told.
+const BENCH_DEPTH: u32 = 4;
+
+/// Talks the Universal Chess Interface, so any chess GUI can play against the engine.
+/// Commands come in one line at a time, the answers go to `output`. Searches run on their own
+/// thread so `stop` can be read while thinking.
+pub struct Uci<W: Write + Send + 'static> {
+    game: Game,
+    output: Arc<Mutex<W>>,
+    stop: Arc<AtomicBool>,
+    search: Option<JoinHandle<()>>,
+    // kept between searches, the search thread holds it while thinking.
+    table: Arc<Mutex<TranspositionTable>>,
+}
+
+impl<W: Write + Send + 'static> Uci<W> {
+    pub fn new(output: W) -> Uci<W> {
+        Uci {
+            game: Game::new(),
+            output: Arc::new(Mutex::new(output)),
+            stop: Arc::new(AtomicBool::new(false)),
+            search: None,
+            table: Arc::new(Mutex::new(TranspositionTable::default())),
+        }
+    }
+
+    pub fn get_game(&self) -> &Game {
+        &self.game
+    }
+
+    pub fn get_output(&self) -> &Arc<Mutex<W>> {
+        &self.output
+    }
+
+    /// Reads commands until `quit` or the end of the input.
+    pub fn run(&mut self, input: impl BufRead) {
+        for line in input.lines() {
+            match line {
+                Ok(line) => {
+                    if !self.handle_command(&line) {
+                        return;
+                    }
+                }
+                Err(_) => break,
+            }
+        }
+        self.stop_search();
+    }
+
+    /// Handles a line from the GUI, false once it's time to quit.
+    /// Unknown commands are ignored like the protocol asks.
+    pub fn handle_command(&mut self, line: &str) -> bool {
+        let mut tokens = line.split_whitespace();
+        match tokens.next() {
+            Some("uci") => {
+                send(&self.output, &format!("id name {}", ENGINE_NAME));
+                send(&self.output, &format!("id author {}", ENGINE_AUTHOR));
+                send(
+                    &self.output,
+                    &format!(
+                        "option name Hash type spin default {} min 1 max {}",
+                        DEFAULT_HASH_SIZE, MAX_HASH_SIZE
+                    ),
+                );
+                send(&self.output, "uciok");
+            }
+            Some("isready") => send(&self.output, "readyok"),
+            Some("ucinewgame") => {
+                self.stop_search();
+                self.game = Game::new();
+                self.lock_table().clear();
+            }
+            Some("setoption") => {
+                self.stop_search();
+                if let Err(error) = self.set_option(tokens) {
+                    send(&self.output, &format!("info string {}", error));
+                }
+            }
+            Some("position") => {
+                self.stop_search();
+                if let Err(error) = self.set_position(tokens) {
+                    send(&self.output, &format!("info string {}", error));
+                }
+            }
+            Some("go") => self.go(tokens),
+            Some("stop") => self.stop_search(),
+            // not part of UCI, searches the bench positions and counts the nodes.
+            Some("bench") => {
+                self.stop_search();
+                let depth = tokens
+                    .next()
+                    .and_then(|depth| depth.parse().ok())
+                    .unwrap_or(BENCH_DEPTH);
+                let mut nodes = 0;
+                let mut time = Duration::ZERO;
+                for (fen, result) in bench(depth) {
+                    send(
+                        &self.output,
+                        &format!("info string {} nodes {}", fen, result.nodes),
+                    );
+                    nodes += result.nodes;
+                    time += result.time;
+                }
+                send(
+                    &self.output,
+                    &format!(
+                        "info string Nodes searched: {} time {} nps {}",
+                        nodes,
+                        time.as_millis(),
+                        (nodes as f64 / time.as_secs_f64().max(0.001)) as u64
+                    ),
+                );
+            }
+            Some("quit") => {
+                self.stop_search();
+                return false;
+            }
+            _ => {}
+        }
+        true
+    }
+
+    /// `startpos` or `fen` and the six FEN fields, then optionally `moves` and the moves played
+    /// since in coordinate notation.
+    fn set_position<'a>(
+        &mut self,
+        mut tokens: impl Iterator<Item = &'a str>,
+    ) -> Result<(), String> {
+        let mut game = match tokens.next() {
+            Some("startpos") => Game::new(),
+            Some("fen") => {
+                let fen: Vec<&str> = tokens
+                    .by_ref()
+                    .take_while(|token| *token != "moves")
+                    .collect();
+                Game::from_fen(&fen.join(" ")).map_err(|error| error.to_string())?
+            }
+            _ => return Err(String::from("Expected startpos or fen.")),
+        };
+        for token in tokens.filter(|token| *token != "moves") {
+            let movement = game.parse_coordinate_notation(token)?;
+            game.play_movement(&movement)?;
+        }
+        self.game = game;
+        Ok(())
+    }
+
+    /// `name Hash value 64`, the only option there is for now.
+    fn set_option<'a>(&mut self, tokens: impl Iterator<Item = &'a str>) -> Result<(), String> {
+        let tokens: Vec<&str> = tokens.collect();
+        let value_at = tokens.iter().position(|token| *token == "value");
+        let name = tokens[..value_at.unwrap_or(tokens.len())]
+            .iter()
+            .skip_while(|token| **token == "name")
+            .copied()
+            .collect::<Vec<&str>>()
+            .join(" ");
+        let value = value_at.map(|i| tokens[i + 1..].join(" "));
+        match name.to_ascii_lowercase().as_str() {
+            "hash" => {
+                let megabytes = value
+                    .and_then(|value| value.parse::<usize>().ok())
+                    .filter(|megabytes| (1..=MAX_HASH_SIZE).contains(megabytes))
+                    .ok_or_else(|| format!("Hash has to be between 1 and {}.", MAX_HASH_SIZE))?;
+                *self.lock_table() = TranspositionTable::new(megabytes);
+                Ok(())
+            }
+            _ => Err(format!("No option named {}.", name)),
+        }
+    }
+
+    fn lock_table(&self) -> MutexGuard<'_, TranspositionTable> {
+        self.table.lock().expect("the table is never poisoned")
+    }
+
+    fn go<'a>(&mut self, mut tokens: impl Iterator<Item = &'a str>) {
+        self.stop_search();
+        let mut limits = SearchLimits::default();
+        let mut infinite = false;
+        let mut clocks = [None, None];
+        let mut increments = [Duration::ZERO, Duration::ZERO];
+        let mut moves_to_go = None;
+        while let Some(token) = tokens.next() {
+            let mut number = || tokens.next().and_then(|value| value.parse::<u64>().ok());
+            match token {
+                "depth" => limits.depth = number().map(|depth| depth as u32),
+                "nodes" => limits.nodes = number(),
+                "movetime" => limits.time = number().map(Duration::from_millis),
+                "wtime" => clocks[0] = number().map(Duration::from_millis),
+                "btime" => clocks[1] = number().map(Duration::from_millis),
+                "winc" => increments[0] = Duration::from_millis(number().unwrap_or(0)),
+                "binc" => increments[1] = Duration::from_millis(number().unwrap_or(0)),
+                "movestogo" => moves_to_go = number().map(|moves| moves as u32),
+                "infinite" => infinite = true,
+                _ => {}
+            }
+        }
+        let side = match self.game.get_current_color() {
+            Color::White => 0,
+            Color::Black => 1,
+        };
+        limits.time_control = clocks[side].map(|time_left| TimeControl {
+            time_left,
+            increment: increments[side],
+            moves_to_go,
+        });
+
+        self.stop.store(false, Ordering::Relaxed);
+        limits.stop = Some(self.stop.clone());
+        let game = self.game.clone();
+        let output = self.output.clone();
+        let stop = self.stop.clone();
+        let table = self.table.clone();
+        self.search = Some(thread::spawn(move || {
+            let mut table = table.lock().expect("the table is never poisoned");
+            let result = game.search_with_info(&limits, &mut table, |result| {
+                send(&output, &get_info(result));
+            });
+            // an infinite search only answers once it's told to stop.
+            while infinite && !stop.load(Ordering::Relaxed) {
+                thread::sleep(Duration::from_millis(5));
+            }
+            let best_move = match result.best_move {
+                Some(movement) => movement.to_string(),
+                None => String::from("0000"),
+            };
+            send(&output, &format!("bestmove {}", best_move));
+        }));
+    }
+
+    /// Stops the search if there's one and waits for it to send its move.
+    fn stop_search(&mut self) {
+        if let Some(search) = self.search.take() {
+            self.stop.store(true, Ordering::Relaxed);
+            search.join().expect("the search thread doesn't panic");
+        }
+    }
+}
+
+pub(crate) fn send<W: Write>(output: &Mutex<W>, message: &str) {
+    let mut output = output.lock().expect("the output is never poisoned");
+    // the GUI going away isn't something we can do anything about.
+    writeln!(output, "{}", message).ok();
+    output.flush().ok();
+}
+
+/// The `info` line for a finished depth, e.g.
+/// `info depth 3 score cp 25 nodes 1234 time 12 hashfull 5 pv e2e4 e7e5 g1f3`.
+pub fn get_info(result: &SearchResult) -> String {
+    let score = match result.get_mate_in() {
+        Some(moves) => format!("mate {}", moves),
+        None => format!("cp {}", result.score),
+    };
+    let mut info = format!(
+        "info depth {} score {} nodes {} time {} hashfull {}",
+        result.depth,
+        score,
+        result.nodes,
+        result.time.as_millis(),
+        result.hashfull
+    );
+    if !result.principal_variation.is_empty() {
+        let movements: Vec<String> = result
+            .principal_variation
+            .iter()
+            .map(|movement| movement.to_string())
+            .collect();
+        info.push_str(&format!(" pv {}", movements.join(" ")));
+    }
+    info
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use crate::engine::bench::BENCH_POSITIONS;
+    use crate::engine::uci::*;
+
+    fn new_uci() -> Uci<Vec<u8>> {
+        Uci::new(vec![])
+    }
+
+    // lets the search finish on its own.
+    fn wait_for_search(uci: &mut Uci<Vec<u8>>) {
+        if let Some(search) = uci.search.take() {
+            search.join().unwrap();
+        }
+    }
+
+    // everything sent so far, emptying it.
+    fn take_output(uci: &Uci<Vec<u8>>) -> Vec<String> {
+        let mut output = uci.get_output().lock().unwrap();
+        let lines = String::from_utf8(output.clone()).unwrap();
+        output.clear();
+        lines.lines().map(String::from).collect()
+    }
+
+    #[test]
+    fn handshake() {
+        let mut uci = new_uci();
+        assert_eq!(true, uci.handle_command("uci"));
+        let output = take_output(&uci);
+        assert_eq!("id name Chess", output[0]);
+        assert_eq!(Some(&String::from("uciok")), output.last());
+        uci.handle_command("isready");
+        assert_eq!(vec!["readyok"], take_output(&uci));
+        // unknown commands are ignored.
+        assert_eq!(true, uci.handle_command("xyzzy"));
+        assert_eq!(true, uci.handle_command(""));
+        assert_eq!(false, uci.handle_command("quit"));
+    }
+
+    #[test]
+    fn hash_option() {
+        let mut uci = new_uci();
+        uci.handle_command("uci");
+        let output = take_output(&uci);
+        assert_eq!(
+            true,
+            output.contains(&String::from(
+                "option name Hash type spin default 16 min 1 max 4096"
+            ))
+        );
+        uci.handle_command("setoption name Hash value 1");
+        assert_eq!(true, take_output(&uci).is_empty());
+        assert_eq!(
+            TranspositionTable::new(1).get_size(),
+            uci.lock_table().get_size()
+        );
+        for option in [
+            "setoption name Hash value 0",
+            "setoption name Hash value lots",
+            "setoption name Hash",
+            "setoption name Ponder value true",
+        ] {
+            uci.handle_command(option);
+            assert_eq!(true, take_output(&uci)[0].starts_with("info string"));
+        }
+        assert_eq!(
+            TranspositionTable::new(1).get_size(),
+            uci.lock_table().get_size()
+        );
+    }
+
+    #[test]
+    fn positions() {
+        let mut uci = new_uci();
+        uci.handle_command("position startpos moves e2e4 e7e5 g1f3");
+        assert_eq!(
+            "rnbqkbnr/pppp1ppp/8/4p3/4P3/5N2/PPPP1PPP/RNBQKB1R b KQkq - 1 2",
+            uci.get_game().to_fen()
+        );
+        uci.handle_command("position fen 8/4P1k1/8/8/8/8/8/4K3 w - - 0 1 moves e7e8n");
+        assert_eq!("4N3/6k1/8/8/8/8/8/4K3 b - - 0 1", uci.get_game().to_fen());
+        uci.handle_command("ucinewgame");
+        assert_eq!(crate::game::fen::STARTING_POSITION, uci.get_game().to_fen());
+        assert_eq!(true, take_output(&uci).is_empty());
+
+        // a bad position is reported and the last one is kept.
+        uci.handle_command("position startpos moves e2e5");
+        assert_eq!(true, take_output(&uci)[0].starts_with("info string"));
+        assert_eq!(crate::game::fen::STARTING_POSITION, uci.get_game().to_fen());
+    }
+
+    #[test]
+    fn go_depth() {
+        let mut uci = new_uci();
+        uci.handle_command("position fen 6k1/5ppp/8/8/8/8/8/R5K1 w - - 0 1");
+        uci.handle_command("go depth 3");
+        wait_for_search(&mut uci);
+        let output = take_output(&uci);
+        assert_eq!("bestmove a1a8", output.last().unwrap());
+        assert_eq!(true, output[0].starts_with("info depth 1 score "));
+        assert_eq!(
+            true,
+            output.iter().any(|line| line.contains("score mate 1"))
+        );
+    }
+
+    #[test]
+    fn go_infinite_waits_for_stop() {
+        let mut uci = new_uci();
+        uci.handle_command("position startpos");
+        uci.handle_command("go infinite");
+        thread::sleep(Duration::from_millis(50));
+        let output = take_output(&uci);
+        assert_eq!(
+            false,
+            output.iter().any(|line| line.starts_with("bestmove"))
+        );
+        uci.handle_command("stop");
+        let output = take_output(&uci);
+        assert_eq!(true, output.last().unwrap().starts_with("bestmove "));
+    }
+
+    #[test]
+    fn bench_command() {
+        let mut uci = new_uci();
+        uci.handle_command("bench 1");
+        let output = take_output(&uci);
+        assert_eq!(BENCH_POSITIONS.len() + 1, output.len());
+        assert_eq!(
+            true,
+            output
+                .last()
+                .unwrap()
+                .starts_with("info string Nodes searched: ")
+        );
+    }
+
+    #[test]
+    fn no_legal_moves() {
+        let mut uci = new_uci();
+        uci.handle_command("position startpos moves f2f3 e7e5 g2g4 d8h4");
+        uci.handle_command("go depth 2");
+        wait_for_search(&mut uci);
+        assert_eq!(vec!["bestmove 0000"], take_output(&uci));
+    }
+}
@@ -0,0 +1,483 @@
+use std::io::{BufRead, Write};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
+use crate::engine::search::{SearchLimits, SearchResult};
+use crate::engine::time::TimeControl;
+use crate::engine::transposition::TranspositionTable;
+use crate::engine::uci::{send, ENGINE_NAME};
+use crate::game::game::Game;
+use crate::game::movement::Movement;
+use crate::game::pgn::get_result_token;
+use crate::pieces::color::Color;
+
+/// Talks the Chess Engine Communication Protocol (version 2) that XBoard and older tools use.
+/// Unlike UCI the engine keeps the game itself, the GUI only sends the moves played.
+pub struct Xboard<W: Write + Send + 'static> {
+    game: Game,
+    output: Arc<Mutex<W>>,
+    // the color the engine plays, None in force mode where it only keeps track of the moves.
+    engine_color: Option<Color>,
+    // print the search as it goes.
+    post: bool,
+    // set by sd
+    max_depth: Option<u32>,
+    // set by st, the exact time for every move.
+    time_per_move: Option<Duration>,
+    // set by level, moves per time control (0 for the whole game) and the increment.
+    moves_per_session: u32,
+    increment: Duration,
+    // what's left on the engine's clock, set by time.
+    clock: Option<Duration>,
+    stop: Arc<AtomicBool>,
+    // once set the search won't send its move anymore.
+    cancel: Arc<AtomicBool>,
+    // gives back the move if it was sent.
+    search: Option<JoinHandle<Option<Movement>>>,
+    // kept between searches, the search thread holds it while thinking.
+    table: Arc<Mutex<TranspositionTable>>,
+}
+
+impl<W: Write + Send + 'static> Xboard<W> {
+    pub fn new(output: W) -> Xboard<W> {
+        Xboard {
+            game: Game::new(),
+            output: Arc::new(Mutex::new(output)),
+            engine_color: Some(Color::Black),
+            post: false,
+            max_depth: None,
+            time_per_move: None,
+            moves_per_session: 0,
+            increment: Duration::ZERO,
+            clock: None,
+            stop: Arc::new(AtomicBool::new(false)),
+            cancel: Arc::new(AtomicBool::new(false)),
+            search: None,
+            table: Arc::new(Mutex::new(TranspositionTable::default())),
+        }
+    }
+
+    pub fn get_game(&self) -> &Game {
+        &self.game
+    }
+
+    pub fn get_output(&self) -> &Arc<Mutex<W>> {
+        &self.output
+    }
+
+    /// Reads commands until `quit` or the end of the input.
+    pub fn run(&mut self, input: impl BufRead) {
+        for line in input.lines() {
+            match line {
+                Ok(line) => {
+                    if !self.handle_command(&line) {
+                        return;
+                    }
+                }
+                Err(_) => break,
+            }
+        }
+        self.end_search(true);
+    }
+
+    /// Handles a line from the GUI, false once it's time to quit.
+    pub fn handle_command(&mut self, line: &str) -> bool {
+        // plays the engine's move if it was sent since the last command.
+        if self
+            .search
+            .as_ref()
+            .is_some_and(|search| search.is_finished())
+        {
+            self.end_search(false);
+        }
+        let mut tokens = line.split_whitespace();
+        let command = match tokens.next() {
+            None => return true,
+            Some(command) => command,
+        };
+        let argument = tokens.next();
+        match command {
+            "protover" => send(
+                &self.output,
+                &format!(
+                    "feature myname=\"{}\" usermove=1 setboard=1 ping=1 playother=1 colors=0 \
+                     memory=1 sigint=0 sigterm=0 analyze=0 done=1",
+                    ENGINE_NAME
+                ),
+            ),
+            "new" => {
+                self.end_search(true);
+                self.game = Game::new();
+                self.engine_color = Some(Color::Black);
+                self.max_depth = None;
+                self.table
+                    .lock()
+                    .expect("the table is never poisoned")
+                    .clear();
+            }
+            "force" => {
+                self.end_search(true);
+                self.engine_color = None;
+            }
+            "go" => {
+                self.end_search(true);
+                self.engine_color = Some(self.game.get_current_color().clone());
+                self.think();
+            }
+            "playother" => {
+                self.end_search(true);
+                self.engine_color = Some(self.game.get_current_color().opposite());
+            }
+            "usermove" => {
+                self.end_search(true);
+                match argument.map(|notation| self.play(notation)) {
+                    Some(Ok(())) => self.think(),
+                    _ => send(
+                        &self.output,
+                        &format!("Illegal move: {}", argument.unwrap_or_default()),
+                    ),
+                }
+            }
+            "setboard" => {
+                self.end_search(true);
+                let fen: Vec<&str> = argument.into_iter().chain(tokens).collect();
+                match Game::from_fen(&fen.join(" ")) {
+                    Ok(game) => self.game = game,
+                    Err(error) => send(&self.output, &format!("tellusererror {}", error)),
+                }
+            }
+            "undo" => {
+                self.end_search(true);
+                self.game.undo().ok();
+            }
+            "remove" => {
+                self.end_search(true);
+                self.game.undo().ok();
+                self.game.undo().ok();
+            }
+            "result" => {
+                self.end_search(true);
+                self.engine_color = None;
+            }
+            "?" => self.end_search(false),
+            "ping" => send(
+                &self.output,
+                &format!("pong {}", argument.unwrap_or_default()),
+            ),
+            // megabytes for the transposition table.
+            "memory" => {
+                self.end_search(true);
+                if let Some(megabytes) = argument.and_then(|megabytes| megabytes.parse().ok()) {
+                    *self.table.lock().expect("the table is never poisoned") =
+                        TranspositionTable::new(megabytes);
+                }
+            }
+            "post" => self.post = true,
+            "nopost" => self.post = false,
+            "sd" => self.max_depth = argument.and_then(|depth| depth.parse().ok()),
+            "st" => {
+                self.time_per_move = argument
+                    .and_then(|seconds| seconds.parse().ok())
+                    .map(Duration::from_secs)
+            }
+            "level" => {
+                let base = tokens.next();
+                let increment = tokens.next();
+                self.moves_per_session = argument.and_then(|moves| moves.parse().ok()).unwrap_or(0);
+                self.increment = increment
+                    .and_then(|seconds| seconds.parse::<f64>().ok())
+                    .map(Duration::from_secs_f64)
+                    .unwrap_or_default();
+                self.clock = base.and_then(parse_base_time);
+                self.time_per_move = None;
+            }
+            "time" => {
+                self.clock = argument
+                    .and_then(|centiseconds| centiseconds.parse::<u64>().ok())
+                    .map(|centiseconds| Duration::from_millis(centiseconds * 10))
+            }
+            "quit" => {
+                self.end_search(true);
+                return false;
+            }
+            // xboard, accepted, rejected, otim, hard, easy, random, computer... don't change
+            // anything here.
+            _ => {}
+        }
+        true
+    }
+
+    fn play(&mut self, notation: &str) -> Result<(), String> {
+        let movement = self.game.parse_coordinate_notation(notation)?;
+        self.game.play_movement(&movement)
+    }
+
+    fn get_limits(&self) -> SearchLimits {
+        let mut limits = SearchLimits {
+            depth: self.max_depth,
+            ..SearchLimits::default()
+        };
+        match (self.time_per_move, self.clock) {
+            (Some(time), _) => limits.time = Some(time),
+            (None, Some(clock)) => {
+                let moves_to_go = match self.moves_per_session {
+                    0 => None,
+                    moves => Some(moves - (self.game.get_fullmove_number() - 1) % moves),
+                };
+                limits.time_control = Some(TimeControl {
+                    time_left: clock,
+                    increment: self.increment,
+                    moves_to_go,
+                });
+            }
+            (None, None) => {}
+        }
+        // without any limit at all it would think forever.
+        if limits.depth.is_none() && limits.time.is_none() && limits.time_control.is_none() {
+            limits.time = Some(Duration::from_secs(5));
+        }
+        limits
+    }
+
+    /// Starts thinking if it's the engine's turn. The move is sent from the search thread as
+    /// soon as it's found.
+    fn think(&mut self) {
+        if self.game.is_over() || self.engine_color.as_ref() != Some(self.game.get_current_color())
+        {
+            return;
+        }
+        self.stop.store(false, Ordering::Relaxed);
+        self.cancel.store(false, Ordering::Relaxed);
+        let mut limits = self.get_limits();
+        limits.stop = Some(self.stop.clone());
+        let game = self.game.clone();
+        let output = self.output.clone();
+        let cancel = self.cancel.clone();
+        let post = self.post;
+        let table = self.table.clone();
+        self.search = Some(thread::spawn(move || {
+            let mut table = table.lock().expect("the table is never poisoned");
+            let result = game.search_with_info(&limits, &mut table, |result| {
+                if post {
+                    send(&output, &get_thinking(result));
+                }
+            });
+            let movement = result.best_move?;
+            let mut after = game.clone();
+            after
+                .play_movement(&movement)
+                .expect("the engine only plays legal movements");
+            // holding the output so the move can't be cancelled halfway.
+            let mut output = output.lock().expect("the output is never poisoned");
+            if cancel.load(Ordering::Relaxed) {
+                return None;
+            }
+            writeln!(output, "move {}", movement).ok();
+            if let Some(result) = after.get_result() {
+                writeln!(output, "{} {{{}}}", get_result_token(Some(result)), result).ok();
+            }
+            output.flush().ok();
+            Some(movement)
+        }));
+    }
+
+    /// Waits for the search to end and plays its move if it was sent. `cancel` makes sure it
+    /// isn't, otherwise the search is only told to move now.
+    fn end_search(&mut self, cancel: bool) {
+        if let Some(search) = self.search.take() {
+            if cancel {
+                self.cancel.store(true, Ordering::Relaxed);
+            }
+            self.stop.store(true, Ordering::Relaxed);
+            let movement = search.join().expect("the search thread doesn't panic");
+            if let Some(movement) = movement {
+                self.game
+                    .play_movement(&movement)
+                    .expect("the engine only plays legal movements");
+            }
+        }
+    }
+}
+
+/// `level` gives the base time in minutes, e.g. `5`, or minutes and seconds, e.g. `0:30`.
+fn parse_base_time(base: &str) -> Option<Duration> {
+    let (minutes, seconds) = match base.split_once(':') {
+        Some((minutes, seconds)) => (minutes, seconds.parse::<u64>().ok()?),
+        None => (base, 0),
+    };
+    Some(Duration::from_secs(
+        minutes.parse::<u64>().ok()? * 60 + seconds,
+    ))
+}
+
+/// A thinking line, `ply score time nodes pv`, with the time in centiseconds.
+pub fn get_thinking(result: &SearchResult) -> String {
+    let movements: Vec<String> = result
+        .principal_variation
+        .iter()
+        .map(|movement| movement.to_string())
+        .collect();
+    format!(
+        "{} {} {} {} {}",
+        result.depth,
+        result.score,
+        result.time.as_millis() / 10,
+        result.nodes,
+        movements.join(" ")
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use crate::engine::xboard::*;
+    use crate::game::fen::STARTING_POSITION;
+
+    fn new_xboard() -> Xboard<Vec<u8>> {
+        let mut xboard = Xboard::new(vec![]);
+        xboard.handle_command("xboard");
+        xboard.handle_command("protover 2");
+        xboard.handle_command("new");
+        xboard.handle_command("sd 2");
+        take_output(&xboard);
+        xboard
+    }
+
+    // everything sent so far, emptying it.
+    fn take_output(xboard: &Xboard<Vec<u8>>) -> Vec<String> {
+        let mut output = xboard.get_output().lock().unwrap();
+        let lines = String::from_utf8(output.clone()).unwrap();
+        output.clear();
+        lines.lines().map(String::from).collect()
+    }
+
+    // lets the search finish on its own.
+    fn wait_for_move(xboard: &mut Xboard<Vec<u8>>) {
+        if let Some(search) = &xboard.search {
+            while !search.is_finished() {
+                thread::sleep(Duration::from_millis(1));
+            }
+        }
+        xboard.end_search(false);
+    }
+
+    #[test]
+    fn feature_negotiation() {
+        let mut xboard = Xboard::new(vec![]);
+        xboard.handle_command("xboard");
+        xboard.handle_command("protover 2");
+        let output = take_output(&xboard);
+        assert_eq!(1, output.len());
+        assert_eq!(true, output[0].starts_with("feature myname=\"Chess\""));
+        assert_eq!(true, output[0].ends_with("done=1"));
+        xboard.handle_command("accepted usermove");
+        xboard.handle_command("memory 1");
+        assert_eq!(
+            TranspositionTable::new(1).get_size(),
+            xboard.table.lock().unwrap().get_size()
+        );
+        xboard.handle_command("ping 7");
+        assert_eq!(vec!["pong 7"], take_output(&xboard));
+        assert_eq!(false, xboard.handle_command("quit"));
+    }
+
+    #[test]
+    fn engine_replies_to_the_user() {
+        let mut xboard = new_xboard();
+        xboard.handle_command("usermove e2e4");
+        wait_for_move(&mut xboard);
+        let output = take_output(&xboard);
+        assert_eq!(1, output.len());
+        assert_eq!(true, output[0].starts_with("move "));
+        assert_eq!(2, xboard.get_game().get_movements().len());
+        assert_eq!(Color::White, *xboard.get_game().get_current_color());
+
+        xboard.handle_command("usermove e2e5");
+        assert_eq!(vec!["Illegal move: e2e5"], take_output(&xboard));
+        xboard.handle_command("remove");
+        assert_eq!(STARTING_POSITION, xboard.get_game().to_fen());
+    }
+
+    #[test]
+    fn force_mode_and_go() {
+        let mut xboard = new_xboard();
+        xboard.handle_command("force");
+        xboard.handle_command("usermove e2e4");
+        xboard.handle_command("usermove e7e5");
+        assert_eq!(true, take_output(&xboard).is_empty());
+        xboard.handle_command("undo");
+        assert_eq!(1, xboard.get_game().get_movements().len());
+        // the engine takes the side to move, black here.
+        xboard.handle_command("post");
+        xboard.handle_command("go");
+        wait_for_move(&mut xboard);
+        let output = take_output(&xboard);
+        assert_eq!(true, output[0].starts_with("1 "));
+        assert_eq!(true, output.last().unwrap().starts_with("move "));
+        assert_eq!(Color::White, *xboard.get_game().get_current_color());
+    }
+
+    #[test]
+    fn setboard_and_result() {
+        let mut xboard = new_xboard();
+        xboard.handle_command("force");
+        xboard.handle_command("setboard 6k1/5ppp/8/8/8/8/8/R5K1 w - - 0 1");
+        xboard.handle_command("go");
+        wait_for_move(&mut xboard);
+        assert_eq!(
+            vec!["move a1a8", "1-0 {Checkmate! W wins!}"],
+            take_output(&xboard)
+        );
+        xboard.handle_command("result 1-0 {White mates}");
+        xboard.handle_command("setboard not a fen");
+        assert_eq!(true, take_output(&xboard)[0].starts_with("tellusererror"));
+    }
+
+    #[test]
+    fn new_game_cancels_the_search() {
+        let mut xboard = new_xboard();
+        xboard.handle_command("sd 30");
+        xboard.handle_command("go");
+        xboard.handle_command("new");
+        assert_eq!(STARTING_POSITION, xboard.get_game().to_fen());
+        assert_eq!(true, take_output(&xboard).is_empty());
+    }
+
+    #[test]
+    fn time_controls() {
+        let mut xboard = new_xboard();
+        xboard.handle_command("level 40 5 0");
+        xboard.handle_command("time 6000");
+        // a minute left for 40 moves.
+        assert_eq!(
+            Some(TimeControl {
+                time_left: Duration::from_secs(60),
+                increment: Duration::ZERO,
+                moves_to_go: Some(40),
+            }),
+            xboard.get_limits().time_control
+        );
+        xboard.handle_command("level 0 0:30 2");
+        // half a minute for the rest of the game.
+        assert_eq!(
+            Some(TimeControl {
+                time_left: Duration::from_secs(30),
+                increment: Duration::from_secs(2),
+                moves_to_go: None,
+            }),
+            xboard.get_limits().time_control
+        );
+        xboard.handle_command("st 3");
+        assert_eq!(Some(Duration::from_secs(3)), xboard.get_limits().time);
+        assert_eq!(None, xboard.get_limits().time_control);
+        assert_eq!(Some(2), xboard.get_limits().depth);
+        // a new game forgets the depth.
+        xboard.handle_command("new");
+        assert_eq!(None, xboard.get_limits().depth);
+        assert_eq!(Some(Duration::from_secs(330)), parse_base_time("5:30"));
+        assert_eq!(None, parse_base_time("soon"));
+    }
+}
@@ -0,0 +1,356 @@
+use crate::pieces::color::Color;
+use crate::pieces::piece::{PieceType, ValidMovement};
+use crate::pieces::validator::row_column::RowColumn;
+
+/// One bit per square, bit `row * 8 + column`, so bit 0 is a8 and bit 63 is h1 like `squares`.
+pub type Bitboard = u64;
+
+// the squares `Square::White` is used for, a8 is one of them.
+pub const WHITE_SQUARES: Bitboard = 0xaa55_aa55_aa55_aa55;
+
+pub const KNIGHT_ATTACKS: [Bitboard; 64] = leaper_attacks(&[
+    [-2, -1],
+    [-2, 1],
+    [-1, -2],
+    [-1, 2],
+    [1, -2],
+    [1, 2],
+    [2, -1],
+    [2, 1],
+]);
+
+pub const KING_ATTACKS: [Bitboard; 64] = leaper_attacks(&[
+    [-1, -1],
+    [-1, 0],
+    [-1, 1],
+    [0, -1],
+    [0, 1],
+    [1, -1],
+    [1, 0],
+    [1, 1],
+]);
+
+// white pawns go up the board, towards row 0.
+pub const PAWN_ATTACKS: [[Bitboard; 64]; 2] = [
+    leaper_attacks(&[[-1, -1], [-1, 1]]),
+    leaper_attacks(&[[1, -1], [1, 1]]),
+];
+
+const ROOK_DIRECTIONS: [[i8; 2]; 4] = [[-1, 0], [1, 0], [0, -1], [0, 1]];
+const BISHOP_DIRECTIONS: [[i8; 2]; 4] = [[-1, -1], [-1, 1], [1, -1], [1, 1]];
+
+const fn leaper_attacks(offsets: &[[i8; 2]]) -> [Bitboard; 64] {
+    let mut attacks = [0; 64];
+    let mut square = 0;
+    while square < 64 {
+        let mut i = 0;
+        while i < offsets.len() {
+            let row = (square / 8) as i8 + offsets[i][0];
+            let column = (square % 8) as i8 + offsets[i][1];
+            if row >= 0 && row < 8 && column >= 0 && column < 8 {
+                attacks[square] |= 1 << (row * 8 + column);
+            }
+            i += 1;
+        }
+        square += 1;
+    }
+    attacks
+}
+
+/// Walks each direction until the edge of the board or the first piece, which is included.
+fn sliding_attacks(square: usize, occupied: Bitboard, directions: &[[i8; 2]; 4]) -> Bitboard {
+    let mut attacks = 0;
+    for direction in directions {
+        let mut row = (square / 8) as i8 + direction[0];
+        let mut column = (square % 8) as i8 + direction[1];
+        while (0..8).contains(&row) && (0..8).contains(&column) {
+            let bit = 1 << (row * 8 + column);
+            attacks |= bit;
+            if occupied & bit != 0 {
+                break;
+            }
+            row += direction[0];
+            column += direction[1];
+        }
+    }
+    attacks
+}
+
+pub fn rook_attacks(square: usize, occupied: Bitboard) -> Bitboard {
+    sliding_attacks(square, occupied, &ROOK_DIRECTIONS)
+}
+
+pub fn bishop_attacks(square: usize, occupied: Bitboard) -> Bitboard {
+    sliding_attacks(square, occupied, &BISHOP_DIRECTIONS)
+}
+
+pub fn square_index(location: &RowColumn) -> usize {
+    (location.row * 8 + location.column) as usize
+}
+
+pub(crate) fn color_index(color: &Color) -> usize {
+    match color {
+        Color::White => 0,
+        Color::Black => 1,
+    }
+}
+
+// pawn first, king last.
+pub(crate) fn piece_index(piece_type: &PieceType) -> usize {
+    match piece_type {
+        PieceType::Pawn => 0,
+        PieceType::Knight => 1,
+        PieceType::Bishop => 2,
+        PieceType::Rook => 3,
+        PieceType::Queen => 4,
+        PieceType::King => 5,
+    }
+}
+
+/// Where every piece is, one bitboard per color and piece type.
+/// `Board` keeps them in sync with its squares so attacks can be found without looking at every
+/// square.
+#[derive(Clone, Copy, Default)]
+pub struct Bitboards {
+    pieces: [[Bitboard; 6]; 2],
+    colors: [Bitboard; 2],
+}
+
+impl Bitboards {
+    pub fn set(&mut self, location: &RowColumn, color: &Color, piece_type: &PieceType) {
+        let bit = 1 << square_index(location);
+        self.pieces[color_index(color)][piece_index(piece_type)] |= bit;
+        self.colors[color_index(color)] |= bit;
+    }
+
+    pub fn clear(&mut self, location: &RowColumn, color: &Color, piece_type: &PieceType) {
+        let bit = !(1 << square_index(location));
+        self.pieces[color_index(color)][piece_index(piece_type)] &= bit;
+        self.colors[color_index(color)] &= bit;
+    }
+
+    pub fn get(&self, color: &Color, piece_type: &PieceType) -> Bitboard {
+        self.pieces[color_index(color)][piece_index(piece_type)]
+    }
+
+    pub fn get_color(&self, color: &Color) -> Bitboard {
+        self.colors[color_index(color)]
+    }
+
+    pub fn get_occupied(&self) -> Bitboard {
+        self.colors[0] | self.colors[1]
+    }
+
+    /// The color and type of the piece on the square, if any.
+    pub fn get_piece(&self, location: &RowColumn) -> Option<(Color, PieceType)> {
+        let bit = 1 << square_index(location);
+        for color in [Color::White, Color::Black] {
+            if self.get_color(&color) & bit == 0 {
+                continue;
+            }
+            for piece_type in [
+                PieceType::Pawn,
+                PieceType::Knight,
+                PieceType::Bishop,
+                PieceType::Rook,
+                PieceType::Queen,
+                PieceType::King,
+            ] {
+                if self.get(&color, &piece_type) & bit != 0 {
+                    return Some((color, piece_type));
+                }
+            }
+        }
+        None
+    }
+
+    pub fn find_king(&self, color: &Color) -> Option<RowColumn> {
+        let king = self.get(color, &PieceType::King);
+        if king == 0 {
+            return None;
+        }
+        let square = king.trailing_zeros() as u8;
+        Some(RowColumn::new(square / 8, square % 8))
+    }
+
+    /// Every piece of the `attacker` color that could capture on `location`.
+    pub fn get_attackers(&self, location: &RowColumn, attacker: &Color) -> Bitboard {
+        self.get_attackers_through(location, attacker, self.get_occupied())
+    }
+
+    // same as above as if only the `occupied` squares had pieces, e.g. to see what attacks
+    // from behind once a piece is gone.
+    fn get_attackers_through(
+        &self,
+        location: &RowColumn,
+        attacker: &Color,
+        occupied: Bitboard,
+    ) -> Bitboard {
+        let square = square_index(location);
+        let queens = self.get(attacker, &PieceType::Queen);
+        let rooks = self.get(attacker, &PieceType::Rook) | queens;
+        let bishops = self.get(attacker, &PieceType::Bishop) | queens;
+        // a pawn attacks the square if a pawn of the other color there would attack it.
+        let pawn_attacks = PAWN_ATTACKS[1 - color_index(attacker)][square];
+        ((pawn_attacks & self.get(attacker, &PieceType::Pawn))
+            | (KNIGHT_ATTACKS[square] & self.get(attacker, &PieceType::Knight))
+            | (KING_ATTACKS[square] & self.get(attacker, &PieceType::King))
+            | (rook_attacks(square, occupied) & rooks)
+            | (bishop_attacks(square, occupied) & bishops))
+            & occupied
+    }
+
+    /// What the piece on `from` wins, in centipawns, capturing on `to` when both sides keep
+    /// recapturing with their least valuable piece for as long as it pays off.
+    /// Pins and checks are ignored.
+    pub fn get_static_exchange(&self, from: &RowColumn, to: &RowColumn) -> i32 {
+        let (mut color, piece_type) = match self.get_piece(from) {
+            None => return 0,
+            Some(piece) => piece,
+        };
+        let captured = match self.get_piece(to) {
+            Some((_, captured)) => captured.get_value(),
+            // a pawn moving diagonally to an empty square takes en passant.
+            None if piece_type == PieceType::Pawn && from.column != to.column => {
+                PieceType::Pawn.get_value()
+            }
+            None => 0,
+        };
+        // gains[i] is what the side making the i-th capture is up if the exchange stops there.
+        let mut gains = vec![captured];
+        let mut occupied = self.get_occupied() & !(1 << square_index(from));
+        let mut attacker_value = piece_type.get_value();
+        loop {
+            color = color.opposite();
+            let attackers = self.get_attackers_through(to, &color, occupied);
+            let (square, piece_type) = match self.get_least_valuable(attackers, &color) {
+                None => break,
+                Some(attacker) => attacker,
+            };
+            gains.push(attacker_value - gains[gains.len() - 1]);
+            occupied &= !(1 << square);
+            attacker_value = piece_type.get_value();
+        }
+        // each side can stop recapturing when it would lose more.
+        while gains.len() > 1 {
+            let last = gains.pop().unwrap();
+            let previous = gains.len() - 1;
+            gains[previous] = gains[previous].min(-last);
+        }
+        gains[0]
+    }
+
+    fn get_least_valuable(&self, pieces: Bitboard, color: &Color) -> Option<(usize, PieceType)> {
+        [
+            PieceType::Pawn,
+            PieceType::Knight,
+            PieceType::Bishop,
+            PieceType::Rook,
+            PieceType::Queen,
+            PieceType::King,
+        ]
+        .into_iter()
+        .find_map(|piece_type| {
+            let found = pieces & self.get(color, &piece_type);
+            (found != 0).then(|| (found.trailing_zeros() as usize, piece_type))
+        })
+    }
+
+    pub fn is_square_attacked(&self, location: &RowColumn, attacker: &Color) -> bool {
+        self.get_attackers(location, attacker) != 0
+    }
+
+    pub fn is_king_in_check(&self, color: &Color) -> bool {
+        match self.find_king(color) {
+            None => false,
+            Some(king) => self.is_square_attacked(&king, &color.opposite()),
+        }
+    }
+
+    /// Same as `Board::apply_movement`, a promoted pawn becomes a queen here.
+    pub fn apply_movement(
+        &mut self,
+        from: &RowColumn,
+        to: &RowColumn,
+        valid_movement: &ValidMovement,
+    ) {
+        let (color, mut piece_type) = match self.get_piece(from) {
+            None => return,
+            Some(piece) => piece,
+        };
+        self.clear(from, &color, &piece_type);
+        match valid_movement {
+            ValidMovement::CASTLING(location) => {
+                self.clear(&location.from, &color, &PieceType::Rook);
+                self.set(&location.to, &color, &PieceType::Rook);
+            }
+            ValidMovement::EnPassant(location) => {
+                self.clear(location, &color.opposite(), &PieceType::Pawn);
+            }
+            ValidMovement::Promotion => piece_type = PieceType::Queen,
+            ValidMovement::VALID | ValidMovement::INVALID => {}
+        }
+        if let Some((captured_color, captured_type)) = self.get_piece(to) {
+            self.clear(to, &captured_color, &captured_type);
+        }
+        self.set(to, &color, &piece_type);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::game::bitboard::*;
+
+    fn bits(squares: &[(u8, u8)]) -> Bitboard {
+        squares
+            .iter()
+            .fold(0, |bits, (row, column)| bits | 1 << (row * 8 + column))
+    }
+
+    #[test]
+    fn attack_tables() {
+        // knight on a8
+        assert_eq!(bits(&[(1, 2), (2, 1)]), KNIGHT_ATTACKS[0]);
+        // king on h1
+        assert_eq!(bits(&[(6, 6), (6, 7), (7, 6)]), KING_ATTACKS[63]);
+        // white pawn on e2 attacks d3 and f3, a black one on e7 attacks d6 and f6.
+        assert_eq!(bits(&[(5, 3), (5, 5)]), PAWN_ATTACKS[0][6 * 8 + 4]);
+        assert_eq!(bits(&[(2, 3), (2, 5)]), PAWN_ATTACKS[1][8 + 4]);
+        // a pawn on the h file only attacks one square.
+        assert_eq!(bits(&[(5, 6)]), PAWN_ATTACKS[0][6 * 8 + 7]);
+    }
+
+    #[test]
+    fn sliding_attacks_stop_at_the_first_piece() {
+        // rook on a1 with a piece on a4 and c1
+        let occupied = bits(&[(4, 0), (7, 2)]);
+        assert_eq!(
+            bits(&[(6, 0), (5, 0), (4, 0), (7, 1), (7, 2)]),
+            rook_attacks(56, occupied)
+        );
+        // bishop on d4 on an empty board sees 13 squares
+        assert_eq!(13, bishop_attacks(4 * 8 + 3, 0).count_ones());
+    }
+
+    #[test]
+    fn attackers() {
+        let mut bitboards = Bitboards::default();
+        let e4 = RowColumn::new(4, 4);
+        bitboards.set(&RowColumn::new(3, 3), &Color::Black, &PieceType::Pawn);
+        bitboards.set(&RowColumn::new(2, 5), &Color::Black, &PieceType::Knight);
+        bitboards.set(&RowColumn::new(4, 0), &Color::Black, &PieceType::Rook);
+        bitboards.set(&RowColumn::new(5, 3), &Color::White, &PieceType::Pawn);
+        // the black pawn on d5 and the knight on f6, the rook on a4 is blocked by nothing.
+        assert_eq!(
+            bits(&[(3, 3), (2, 5), (4, 0)]),
+            bitboards.get_attackers(&e4, &Color::Black)
+        );
+        // the white pawn on d3 attacks e4 too.
+        assert_eq!(bits(&[(5, 3)]), bitboards.get_attackers(&e4, &Color::White));
+        bitboards.set(&RowColumn::new(4, 2), &Color::White, &PieceType::Bishop);
+        assert_eq!(
+            bits(&[(3, 3), (2, 5)]),
+            bitboards.get_attackers(&e4, &Color::Black)
+        );
+    }
+}
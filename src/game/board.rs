@@ -1,15 +1,15 @@
-use std::collections::HashMap;
-
+use crate::game::bitboard::{Bitboards, WHITE_SQUARES};
 use crate::game::square::Square;
 use crate::pieces::color::Color;
-use crate::pieces::piece::{PieceInfo, Pieces};
+use crate::pieces::piece::{PieceInfo, PieceType, Pieces, ValidMovement};
+use crate::pieces::validator::row_column::RowColumn;
 
 // the board will follow the design of wiki.
+#[derive(Clone)]
 pub struct Board {
     pub squares: Vec<Vec<Square>>,
-    // keeps track of how many pieces we have.
-    white_pieces: HashMap<String, u8>,
-    black_pieces: HashMap<String, u8>,
+    // the same pieces as bitboards, to count them and find attacks quickly.
+    bitboards: Bitboards,
 }
 
 impl Board {
@@ -87,8 +87,7 @@ impl Board {
         }
         Board {
             squares,
-            white_pieces: HashMap::new(),
-            black_pieces: HashMap::new(),
+            bitboards: Bitboards::default(),
         }
     }
 
@@ -97,10 +96,6 @@ impl Board {
             vec![Square::White(None); 8]; // Create a 8x8 grid of None squares initially
             8
         ];
-        // keep track of the pieces
-        let pairs = [('R', 2), ('N', 2), ('B', 2), ('Q', 1), ('K', 1), ('P', 8)];
-        let white_pieces = HashMap::from(pairs.map(|p| (format!("W{}", p.0), p.1 as u8)));
-        let black_pieces = HashMap::from(pairs.map(|p| (format!("B{}", p.0), p.1 as u8)));
         for row in 0..squares.len() {
             let white_is_first = row % 2 == 0;
             for column in 0..squares[row].len() {
@@ -117,53 +112,56 @@ impl Board {
                 }
             }
         }
-        Board {
-            squares,
-            white_pieces,
-            black_pieces,
+        // keep track of the pieces
+        let mut bitboards = Bitboards::default();
+        for (row, squares) in squares.iter().enumerate() {
+            for (column, square) in squares.iter().enumerate() {
+                if let Some(piece) = square.get_piece() {
+                    bitboards.set(
+                        &RowColumn::new(row as u8, column as u8),
+                        piece.get_color(),
+                        &piece.get_piece_type(),
+                    );
+                }
+            }
         }
+        Board { squares, bitboards }
     }
 
-    fn decrement(&self, ref_count: Option<&mut u8>) {
-        match ref_count {
-            None => {}
-            Some(ref_count) => {
-                *ref_count -= 1;
-            }
-        }
+    /// How many pieces of that type and color are still on the board.
+    pub fn get_piece_count(&self, color: &Color, piece_type: &PieceType) -> u8 {
+        self.bitboards.get(color, piece_type).count_ones() as u8
     }
 
-    pub fn remove_piece_from_map(&mut self, piece: Pieces) {
-        let lambda = |map: &mut HashMap<String, u8>, key: &str| -> () {
-            match map.get_mut(key) {
-                Some(count) => {
-                    *count -= 1;
-                    if *count == 0 {
-                        map.remove(key);
-                    }
-                }
-                None => {}
-            }
-        };
-        match piece.get_color() {
-            Color::White => {
-                let map = &mut self.white_pieces;
-                lambda(map, &piece.get_name());
-            }
-            Color::Black => {
-                let map = &mut self.black_pieces;
-                lambda(map, &piece.get_name());
-            }
-        }
+    pub fn get_bitboards(&self) -> &Bitboards {
+        &self.bitboards
     }
 
     /// Given a row and column, replace the piece in the square with the one passed.
-    /// If the square had a piece, we'll return it.
+    /// If the square had a piece, it's captured and no longer counted.
     pub fn set_piece(&mut self, row: u8, column: u8, piece: Pieces) {
+        // whatever was there is captured
+        self.take_piece(row, column);
+        self.bitboards.set(
+            &RowColumn::new(row, column),
+            piece.get_color(),
+            &piece.get_piece_type(),
+        );
         let to_square = &mut self.squares[row as usize][column as usize];
         to_square.set_piece(piece)
     }
 
+    /// Picks up the piece in the square, if any. It's no longer counted until it's set again.
+    pub fn take_piece(&mut self, row: u8, column: u8) -> Option<Pieces> {
+        let piece = self.squares[row as usize][column as usize].get_actual_piece()?;
+        self.bitboards.clear(
+            &RowColumn::new(row, column),
+            piece.get_color(),
+            &piece.get_piece_type(),
+        );
+        Some(piece)
+    }
+
     pub fn create_new_piece_and_set_as_moved(
         &self,
         c: char,
@@ -198,9 +196,149 @@ impl Board {
     }
 
     pub fn remove_piece(&mut self, row: u8, column: u8) {
-        let to_square = &mut self.squares[row as usize][column as usize];
-        to_square.remove_piece()
+        self.take_piece(row, column);
+    }
+
+    /// Whether neither player has enough pieces left to ever checkmate the other.
+    /// That's a lone king against a king and at most a knight, or only bishops that all stand
+    /// on squares of the same color.
+    pub fn has_insufficient_material(&self) -> bool {
+        for color in [Color::White, Color::Black] {
+            for piece_type in [PieceType::Pawn, PieceType::Rook, PieceType::Queen] {
+                if self.get_piece_count(&color, &piece_type) > 0 {
+                    return false;
+                }
+            }
+        }
+        let knights = self.get_piece_count(&Color::White, &PieceType::Knight)
+            + self.get_piece_count(&Color::Black, &PieceType::Knight);
+        let bishops = self.get_piece_count(&Color::White, &PieceType::Bishop)
+            + self.get_piece_count(&Color::Black, &PieceType::Bishop);
+        if knights == 0 && bishops == 0 {
+            return true;
+        }
+        if knights == 1 && bishops == 0 {
+            return true;
+        }
+        if knights > 0 {
+            return false;
+        }
+        let bishop_squares = self.bitboards.get(&Color::White, &PieceType::Bishop)
+            | self.bitboards.get(&Color::Black, &PieceType::Bishop);
+        let on_white_squares = bishop_squares & WHITE_SQUARES != 0;
+        let on_black_squares = bishop_squares & !WHITE_SQUARES != 0;
+        !(on_white_squares && on_black_squares)
+    }
+    /// Moves the piece in `from` to `to` according to what the validators told us.
+    /// Castling also moves the rook, en passant removes the pawn that was passed and a promotion
+    /// replaces the pawn with `promoted_piece`.
+    pub fn apply_movement(
+        &mut self,
+        from: &RowColumn,
+        to: &RowColumn,
+        valid_movement: &ValidMovement,
+        promoted_piece: Option<Pieces>,
+    ) {
+        let mut piece = match self.take_piece(from.row, from.column) {
+            None => return,
+            Some(piece) => piece,
+        };
+        piece.set_as_moved();
+        match valid_movement {
+            ValidMovement::CASTLING(location) => {
+                if let Some(mut rook) = self.take_piece(location.from.row, location.from.column) {
+                    rook.set_as_moved();
+                    self.set_piece(location.to.row, location.to.column, rook);
+                } else {
+                    panic!("bug!! Rook should have been around!!")
+                }
+            }
+            ValidMovement::EnPassant(row_column) => {
+                self.remove_piece(row_column.row, row_column.column);
+            }
+            ValidMovement::Promotion => {
+                if let Some(new_piece) = promoted_piece {
+                    piece = new_piece;
+                }
+            }
+            ValidMovement::VALID | ValidMovement::INVALID => {}
+        }
+        self.set_piece(to.row, to.column, piece);
+    }
+
+    /// The pieces of each row, from the 8th to the 1st, the way FEN writes them.
+    /// White pieces are uppercase, black ones lowercase and empty squares are counted.
+    /// e.g. `rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR` for a new board.
+    pub fn get_placement(&self) -> String {
+        let mut rows = vec![];
+        for row in &self.squares {
+            let mut placement = String::new();
+            let mut empty_squares = 0;
+            for square in row {
+                match square.get_piece() {
+                    None => empty_squares += 1,
+                    Some(piece) => {
+                        if empty_squares > 0 {
+                            placement.push_str(&empty_squares.to_string());
+                            empty_squares = 0;
+                        }
+                        let name = piece.get_piece_type().get_name();
+                        placement.push(match piece.get_color() {
+                            Color::White => name,
+                            Color::Black => name.to_ascii_lowercase(),
+                        });
+                    }
+                }
+            }
+            if empty_squares > 0 {
+                placement.push_str(&empty_squares.to_string());
+            }
+            rows.push(placement);
+        }
+        rows.join("/")
     }
+
+    /// Looks for the king of the given color.
+    /// Returns None if that king isn't on the board(some test boards don't have one).
+    pub fn find_king(&self, color: &Color) -> Option<RowColumn> {
+        self.bitboards.find_king(color)
+    }
+
+    /// Checks if any piece of the `attacker` color could capture a piece standing on `location`.
+    /// Castling and en passant aren't attacks, so they're ignored.
+    pub fn is_square_attacked(&self, location: &RowColumn, attacker: &Color) -> bool {
+        self.bitboards.is_square_attacked(location, attacker)
+    }
+
+    /// Whether the king of the given color is currently under attack.
+    pub fn is_king_in_check(&self, color: &Color) -> bool {
+        self.bitboards.is_king_in_check(color)
+    }
+
+    /// What the piece on `from` wins capturing on `to` once all the recaptures are done, in
+    /// centipawns. Negative when the capture loses material.
+    pub fn get_static_exchange(&self, from: &RowColumn, to: &RowColumn) -> i32 {
+        self.bitboards.get_static_exchange(from, to)
+    }
+
+    /// Whether the other side wins material capturing the piece on `location`.
+    pub fn is_hanging(&self, location: &RowColumn) -> bool {
+        let color = match self.bitboards.get_piece(location) {
+            None => return false,
+            Some((color, _)) => color,
+        };
+        let mut attackers = self.bitboards.get_attackers(location, &color.opposite());
+        while attackers != 0 {
+            let square = attackers.trailing_zeros() as u8;
+            let from = RowColumn::new(square / 8, square % 8);
+            if self.get_static_exchange(&from, location) > 0 {
+                return true;
+            }
+            attackers &= attackers - 1;
+        }
+        false
+    }
+
     /// Simply prints the alpha for a board' column
     ///
     /// # Arguments
@@ -245,3 +383,181 @@ impl Board {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::game::board::Board;
+    use crate::pieces::color::Color;
+    use crate::pieces::piece::{PieceInfo, PieceType, ValidMovement};
+    use crate::pieces::validator::row_column::RowColumn;
+
+    fn place(board: &mut Board, row: u8, column: u8, piece_type: PieceType, color: Color) {
+        board.set_piece(
+            row,
+            column,
+            piece_type.create_actual_piece(PieceInfo::new(color, true)),
+        );
+    }
+
+    #[test]
+    fn piece_counts_follow_captures_and_promotions() {
+        let mut board = Board::new();
+        assert_eq!(8, board.get_piece_count(&Color::White, &PieceType::Pawn));
+        assert_eq!(1, board.get_piece_count(&Color::Black, &PieceType::Queen));
+
+        // white pawn on a2 takes the black pawn on b7, as if it got there.
+        board.apply_movement(
+            &RowColumn::new(6, 0),
+            &RowColumn::new(1, 1),
+            &ValidMovement::VALID,
+            None,
+        );
+        assert_eq!(8, board.get_piece_count(&Color::White, &PieceType::Pawn));
+        assert_eq!(7, board.get_piece_count(&Color::Black, &PieceType::Pawn));
+
+        // and then promotes by taking the rook on a8.
+        let queen = board.create_new_piece_and_set_as_moved('Q', false);
+        board.apply_movement(
+            &RowColumn::new(1, 1),
+            &RowColumn::new(0, 0),
+            &ValidMovement::Promotion,
+            queen,
+        );
+        assert_eq!(7, board.get_piece_count(&Color::White, &PieceType::Pawn));
+        assert_eq!(2, board.get_piece_count(&Color::White, &PieceType::Queen));
+        assert_eq!(1, board.get_piece_count(&Color::Black, &PieceType::Rook));
+    }
+
+    #[test]
+    fn insufficient_material() {
+        let mut board = Board::create_empty_board();
+        place(&mut board, 7, 4, PieceType::King, Color::White);
+        place(&mut board, 0, 4, PieceType::King, Color::Black);
+        assert_eq!(true, board.has_insufficient_material());
+
+        // king and knight against a king
+        place(&mut board, 5, 5, PieceType::Knight, Color::White);
+        assert_eq!(true, board.has_insufficient_material());
+
+        // two knights could mate with some help
+        place(&mut board, 2, 2, PieceType::Knight, Color::Black);
+        assert_eq!(false, board.has_insufficient_material());
+        board.remove_piece(2, 2);
+        board.remove_piece(5, 5);
+
+        // bishops on the same color of squares, a8 and c6 are both white squares
+        place(&mut board, 0, 0, PieceType::Bishop, Color::White);
+        assert_eq!(true, board.has_insufficient_material());
+        place(&mut board, 2, 2, PieceType::Bishop, Color::Black);
+        assert_eq!(true, board.has_insufficient_material());
+
+        // b8 is a black square
+        place(&mut board, 0, 1, PieceType::Bishop, Color::Black);
+        assert_eq!(false, board.has_insufficient_material());
+        board.remove_piece(0, 1);
+
+        place(&mut board, 3, 3, PieceType::Pawn, Color::Black);
+        assert_eq!(false, board.has_insufficient_material());
+    }
+
+    fn assert_bitboards_follow_the_squares(board: &Board) {
+        for row in 0..8 {
+            for column in 0..8 {
+                let location = RowColumn::new(row, column);
+                let piece = board.squares[row as usize][column as usize]
+                    .get_piece()
+                    .as_ref()
+                    .map(|piece| (piece.get_color().clone(), piece.get_piece_type()));
+                assert_eq!(
+                    piece,
+                    board.get_bitboards().get_piece(&location),
+                    "{}",
+                    location
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn bitboards_follow_the_squares() {
+        let mut board = Board::new();
+        assert_bitboards_follow_the_squares(&board);
+        assert_eq!(Some(RowColumn::new(7, 4)), board.find_king(&Color::White));
+        assert_eq!(Some(RowColumn::new(0, 4)), board.find_king(&Color::Black));
+
+        // the pawns on e2 and d7 are gone, the white queen goes to h5 and takes on f7.
+        board.remove_piece(6, 4);
+        board.remove_piece(1, 3);
+        assert_eq!(false, board.is_king_in_check(&Color::Black));
+        board.apply_movement(
+            &RowColumn::new(7, 3),
+            &RowColumn::new(1, 5),
+            &ValidMovement::VALID,
+            None,
+        );
+        assert_bitboards_follow_the_squares(&board);
+        assert_eq!(true, board.is_king_in_check(&Color::Black));
+        assert_eq!(false, board.is_king_in_check(&Color::White));
+        // with d7 empty, the bishop on c8 sees all the way to h3 and the queen down to d2.
+        assert_eq!(
+            true,
+            board.is_square_attacked(&RowColumn::new(5, 7), &Color::Black)
+        );
+        assert_eq!(
+            true,
+            board.is_square_attacked(&RowColumn::new(6, 3), &Color::Black)
+        );
+        assert_eq!(
+            false,
+            board.is_square_attacked(&RowColumn::new(6, 4), &Color::Black)
+        );
+    }
+
+    fn exchange(fen: &str, from: (u8, u8), to: (u8, u8)) -> i32 {
+        let game = crate::game::game::Game::from_fen(fen).unwrap();
+        game.get_board()
+            .get_static_exchange(&RowColumn::new(from.0, from.1), &RowColumn::new(to.0, to.1))
+    }
+
+    #[test]
+    fn static_exchange() {
+        // Rxe5, nothing defends the pawn.
+        let fen = "1k1r4/1pp4p/p7/4p3/8/P5P1/1PP4P/2K1R3 w - - 0 1";
+        assert_eq!(100, exchange(fen, (7, 4), (3, 4)));
+        // Nxe5 Nxe5 Rxe5 Bxe5 Qxe5 Qxe5, white stops after Nxe5 Nxe5 and is down a knight for
+        // a pawn.
+        let fen = "1k1r3q/1ppn3p/p4b2/4p3/8/P2N2P1/1PP1R1BP/2K1Q3 w - - 0 1";
+        assert_eq!(-220, exchange(fen, (5, 3), (3, 4)));
+        // Qxd6 cxd6
+        let fen = "4k3/2p5/3p4/8/8/8/3Q4/4K3 w - - 0 1";
+        assert_eq!(-800, exchange(fen, (6, 3), (2, 3)));
+        // exd5 exd5
+        let fen = "4k3/8/4p3/3n4/4P3/8/8/4K3 w - - 0 1";
+        assert_eq!(220, exchange(fen, (4, 4), (3, 3)));
+        // the king can't take back, the rook behind defends d5.
+        let fen = "8/8/4k3/3r4/8/8/3R4/3RK3 w - - 0 1";
+        assert_eq!(500, exchange(fen, (6, 3), (3, 3)));
+        // en passant
+        let fen = "4k3/8/8/3pP3/8/8/8/4K3 w - d6 0 1";
+        assert_eq!(100, exchange(fen, (3, 4), (2, 3)));
+        // not a capture
+        assert_eq!(0, exchange(fen, (7, 4), (6, 4)));
+    }
+
+    #[test]
+    fn hanging_pieces() {
+        let game =
+            crate::game::game::Game::from_fen("4k3/8/4p3/3n4/4P3/8/8/4K3 w - - 0 1").unwrap();
+        let board = game.get_board();
+        // the pawn takes the knight, even if it's defended.
+        assert_eq!(true, board.is_hanging(&RowColumn::new(3, 3)));
+        // nothing attacks the pawns.
+        assert_eq!(false, board.is_hanging(&RowColumn::new(4, 4)));
+        assert_eq!(false, board.is_hanging(&RowColumn::new(2, 4)));
+        assert_eq!(false, board.is_hanging(&RowColumn::new(5, 5)));
+
+        // taking the defended knight with the rook loses material.
+        let game = crate::game::game::Game::from_fen("4k3/8/4p3/3n4/8/8/8/3RK3 w - - 0 1").unwrap();
+        assert_eq!(false, game.get_board().is_hanging(&RowColumn::new(3, 3)));
+    }
+}
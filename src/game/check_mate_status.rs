@@ -2,4 +2,5 @@ pub enum CheckMateStatus {
     NONE,
     CHECK,
     CHECKMATE,
+    STALEMATE,
 }
@@ -0,0 +1,390 @@
+use std::fmt;
+
+use crate::board_movements::{A, E, H};
+use crate::game::board::Board;
+use crate::pieces::color::Color;
+use crate::pieces::piece::{PieceInfo, PieceType, Pieces};
+use crate::pieces::validator::row_column::RowColumn;
+
+pub static STARTING_POSITION: &str = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1";
+
+#[derive(Debug, Eq, PartialEq)]
+pub enum FenError {
+    // a FEN has six fields separated by spaces.
+    WrongNumberOfFields(usize),
+    // the placement needs eight rows separated by `/`.
+    WrongNumberOfRows(usize),
+    // the row, counting from the 8th, doesn't add up to eight squares.
+    InvalidRow(u8, String),
+    InvalidPiece(char),
+    // pawns can't be on the 1st or 8th rank, they'd have promoted.
+    PawnOnBackRank(u8),
+    // each player needs exactly one king.
+    InvalidKingCount(Color, usize),
+    InvalidSideToMove(String),
+    // the player who just moved can't have left their king in check.
+    OpponentInCheck(Color),
+    InvalidCastlingRights(String),
+    InvalidEnPassant(String),
+    InvalidHalfmoveClock(String),
+    InvalidFullmoveNumber(String),
+}
+
+impl fmt::Display for FenError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FenError::WrongNumberOfFields(count) => {
+                write!(f, "Expected 6 fields but found {}.", count)
+            }
+            FenError::WrongNumberOfRows(count) => write!(f, "Expected 8 rows but found {}.", count),
+            FenError::InvalidRow(row, placement) => {
+                write!(f, "Row {} doesn't have 8 squares: {}", row, placement)
+            }
+            FenError::InvalidPiece(piece) => write!(f, "Unknown piece {}.", piece),
+            FenError::PawnOnBackRank(rank) => write!(f, "There can't be a pawn on rank {}.", rank),
+            FenError::InvalidKingCount(color, count) => write!(
+                f,
+                "{} must have exactly one king but has {}.",
+                color.get_color(),
+                count
+            ),
+            FenError::InvalidSideToMove(side) => {
+                write!(f, "Side to move must be w or b but was {}.", side)
+            }
+            FenError::OpponentInCheck(color) => write!(
+                f,
+                "{} is in check but it's not their turn.",
+                color.get_color()
+            ),
+            FenError::InvalidCastlingRights(rights) => {
+                write!(f, "Invalid castling rights {}.", rights)
+            }
+            FenError::InvalidEnPassant(square) => {
+                write!(f, "Invalid en passant square {}.", square)
+            }
+            FenError::InvalidHalfmoveClock(clock) => write!(f, "Invalid halfmove clock {}.", clock),
+            FenError::InvalidFullmoveNumber(number) => {
+                write!(f, "Invalid fullmove number {}.", number)
+            }
+        }
+    }
+}
+
+/// The fields of a Forsyth–Edwards Notation string.
+/// Kings and rooks are only marked as unmoved if the castling rights say so, and pawns only if
+/// they're still on the row they started on.
+pub struct Fen {
+    pub board: Board,
+    pub current_color: Color,
+    pub en_passant: Option<RowColumn>,
+    pub halfmove_clock: u32,
+    pub fullmove_number: u32,
+}
+
+impl Fen {
+    pub fn parse(fen: &str) -> Result<Fen, FenError> {
+        let fields: Vec<&str> = fen.split_whitespace().collect();
+        if fields.len() != 6 {
+            return Err(FenError::WrongNumberOfFields(fields.len()));
+        }
+        let mut board = Fen::parse_placement(fields[0])?;
+        let current_color = match fields[1] {
+            "w" => Color::White,
+            "b" => Color::Black,
+            side => return Err(FenError::InvalidSideToMove(side.to_string())),
+        };
+        if board.is_king_in_check(&current_color.opposite()) {
+            return Err(FenError::OpponentInCheck(current_color.opposite()));
+        }
+        Fen::parse_castling_rights(fields[2], &mut board)?;
+        let en_passant = Fen::parse_en_passant(fields[3], &board, &current_color)?;
+        let halfmove_clock = fields[4]
+            .parse::<u32>()
+            .map_err(|_| FenError::InvalidHalfmoveClock(fields[4].to_string()))?;
+        let fullmove_number = match fields[5].parse::<u32>() {
+            Ok(number) if number > 0 => number,
+            _ => return Err(FenError::InvalidFullmoveNumber(fields[5].to_string())),
+        };
+        Ok(Fen {
+            board,
+            current_color,
+            en_passant,
+            halfmove_clock,
+            fullmove_number,
+        })
+    }
+
+    fn parse_placement(placement: &str) -> Result<Board, FenError> {
+        let rows: Vec<&str> = placement.split('/').collect();
+        if rows.len() != 8 {
+            return Err(FenError::WrongNumberOfRows(rows.len()));
+        }
+        let mut board = Board::create_empty_board();
+        for (row, pieces) in rows.iter().enumerate() {
+            let invalid_row = || FenError::InvalidRow(8 - row as u8, pieces.to_string());
+            let mut column: u8 = 0;
+            for c in pieces.chars() {
+                if let Some(empty_squares) = c.to_digit(10) {
+                    // checked before adding so a long row can't overflow.
+                    if empty_squares == 0 || column as u32 + empty_squares > 8 {
+                        return Err(invalid_row());
+                    }
+                    column += empty_squares as u8;
+                    continue;
+                }
+                if column >= 8 {
+                    return Err(invalid_row());
+                }
+                let piece = Fen::create_piece(c, row as u8)?;
+                board.set_piece(row as u8, column, piece);
+                column += 1;
+            }
+            if column != 8 {
+                return Err(invalid_row());
+            }
+        }
+        for color in [Color::White, Color::Black] {
+            let kings = board.get_piece_count(&color, &PieceType::King) as usize;
+            if kings != 1 {
+                return Err(FenError::InvalidKingCount(color, kings));
+            }
+        }
+        Ok(board)
+    }
+
+    /// Kings and rooks start as moved, the castling rights will say otherwise.
+    fn create_piece(c: char, row: u8) -> Result<Pieces, FenError> {
+        let piece_type = PieceType::from_char(c).ok_or(FenError::InvalidPiece(c))?;
+        if piece_type == PieceType::Pawn && (row == 0 || row == 7) {
+            return Err(FenError::PawnOnBackRank(8 - row));
+        }
+        let color = if c.is_ascii_uppercase() {
+            Color::White
+        } else {
+            Color::Black
+        };
+        let starting_pawn_row = if matches!(color, Color::White) { 6 } else { 1 };
+        let track_movement = matches!(
+            piece_type,
+            PieceType::Pawn | PieceType::King | PieceType::Rook
+        );
+        let mut piece = piece_type.create_actual_piece(PieceInfo::new(color, track_movement));
+        if piece_type != PieceType::Pawn || row != starting_pawn_row {
+            piece.set_as_moved();
+        }
+        Ok(piece)
+    }
+
+    fn parse_castling_rights(rights: &str, board: &mut Board) -> Result<(), FenError> {
+        if rights == "-" {
+            return Ok(());
+        }
+        let invalid_rights = || FenError::InvalidCastlingRights(rights.to_string());
+        for (i, c) in rights.char_indices() {
+            if rights[..i].contains(c) {
+                return Err(invalid_rights());
+            }
+            let (color, row) = if c.is_ascii_uppercase() {
+                (Color::White, 7)
+            } else {
+                (Color::Black, 0)
+            };
+            let rook_column = match c.to_ascii_uppercase() {
+                'K' => H,
+                'Q' => A,
+                _ => return Err(invalid_rights()),
+            };
+            for (column, piece_type) in [(E, PieceType::King), (rook_column, PieceType::Rook)] {
+                let square = &board.squares[row as usize][column as usize];
+                if square.get_piece_type() != Some(piece_type)
+                    || square.get_color_of_piece() != Some(color.clone())
+                {
+                    return Err(invalid_rights());
+                }
+                let mut piece = board.take_piece(row, column).unwrap();
+                if let Pieces::King(info) | Pieces::Rook(info) = &mut piece {
+                    *info = PieceInfo::new(color.clone(), true);
+                }
+                board.set_piece(row, column, piece);
+            }
+        }
+        Ok(())
+    }
+
+    /// The square has to be right behind a pawn of the other player that could have just moved
+    /// two squares.
+    fn parse_en_passant(
+        square: &str,
+        board: &Board,
+        current_color: &Color,
+    ) -> Result<Option<RowColumn>, FenError> {
+        if square == "-" {
+            return Ok(None);
+        }
+        let invalid_en_passant = || FenError::InvalidEnPassant(square.to_string());
+        let mut chars = square.chars();
+        let (column, rank) = match (chars.next(), chars.next(), chars.next()) {
+            (Some(column @ 'a'..='h'), Some(rank @ ('3' | '6')), None) => (column, rank),
+            _ => return Err(invalid_en_passant()),
+        };
+        let (expected_rank, pawn_row) = match current_color {
+            Color::White => ('6', 3),
+            Color::Black => ('3', 4),
+        };
+        if rank != expected_rank {
+            return Err(invalid_en_passant());
+        }
+        let column = column as u8 - b'a';
+        let pawn = &board.squares[pawn_row][column as usize];
+        if pawn.get_piece_type() != Some(PieceType::Pawn)
+            || pawn.get_color_of_piece() != Some(current_color.opposite())
+        {
+            return Err(invalid_en_passant());
+        }
+        let row = 8 - rank.to_digit(10).unwrap() as u8;
+        // both the square it skipped and the one it came from must be empty.
+        let starting_row = if pawn_row == 3 { 1 } else { 6 };
+        if board.squares[row as usize][column as usize].has_piece()
+            || board.squares[starting_row][column as usize].has_piece()
+        {
+            return Err(invalid_en_passant());
+        }
+        Ok(Some(RowColumn::new(row, column)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::game::fen::{Fen, FenError, STARTING_POSITION};
+    use crate::pieces::color::Color;
+    use crate::pieces::piece::PieceType;
+    use crate::pieces::validator::row_column::RowColumn;
+
+    #[test]
+    fn starting_position() {
+        let fen = Fen::parse(STARTING_POSITION).unwrap();
+        assert_eq!(
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR",
+            fen.board.get_placement()
+        );
+        assert_eq!(Color::White, fen.current_color);
+        assert_eq!(None, fen.en_passant);
+        assert_eq!(0, fen.halfmove_clock);
+        assert_eq!(1, fen.fullmove_number);
+        assert_eq!(
+            8,
+            fen.board.get_piece_count(&Color::Black, &PieceType::Pawn)
+        );
+    }
+
+    #[test]
+    fn castling_rights_and_pawns_set_has_moved() {
+        let fen = Fen::parse("r3k2r/p6p/8/8/8/P7/7P/R3K2R w Kq - 0 1").unwrap();
+        let has_moved = |row: usize, column: usize| {
+            fen.board.squares[row][column]
+                .get_piece_info()
+                .unwrap()
+                .get_has_moved()
+        };
+        // white king, h1 rook and a1 rook
+        assert_eq!(false, has_moved(7, 4));
+        assert_eq!(false, has_moved(7, 7));
+        assert_eq!(true, has_moved(7, 0));
+        // black king, a8 rook and h8 rook
+        assert_eq!(false, has_moved(0, 4));
+        assert_eq!(false, has_moved(0, 0));
+        assert_eq!(true, has_moved(0, 7));
+        // pawns that left their starting row have moved
+        assert_eq!(true, has_moved(5, 0));
+        assert_eq!(false, has_moved(6, 7));
+        assert_eq!(false, has_moved(1, 0));
+    }
+
+    #[test]
+    fn en_passant_square() {
+        let fen =
+            Fen::parse("rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq e3 0 1").unwrap();
+        assert_eq!(Some(RowColumn::new(5, 4)), fen.en_passant);
+    }
+
+    #[test]
+    fn malformed_fens() {
+        // would overflow if the squares were added up before checking.
+        let long_row = format!(
+            "rnbqkbnr/{}/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+            "8".repeat(32)
+        );
+        let cases = [
+            (
+                "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq -",
+                FenError::WrongNumberOfFields(4),
+            ),
+            (
+                "rnbqkbnr/pppppppp/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+                FenError::WrongNumberOfRows(7),
+            ),
+            (
+                "rnbqkbnr/pppppppp/9/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+                FenError::InvalidRow(6, String::from("9")),
+            ),
+            (
+                "rnbqkbnr/ppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+                FenError::InvalidRow(7, String::from("ppppppp")),
+            ),
+            (
+                "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNRR w KQkq - 0 1",
+                FenError::InvalidRow(1, String::from("RNBQKBNRR")),
+            ),
+            (&long_row, FenError::InvalidRow(7, "8".repeat(32))),
+            (
+                "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNX w KQkq - 0 1",
+                FenError::InvalidPiece('X'),
+            ),
+            ("k6P/8/8/8/8/8/8/7K w - - 0 1", FenError::PawnOnBackRank(8)),
+            ("k7/8/8/8/8/8/8/p6K w - - 0 1", FenError::PawnOnBackRank(1)),
+            (
+                "rnbq1bnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQ - 0 1",
+                FenError::InvalidKingCount(Color::Black, 0),
+            ),
+            (
+                "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR x KQkq - 0 1",
+                FenError::InvalidSideToMove(String::from("x")),
+            ),
+            (
+                "k7/8/8/8/8/8/8/R6K w - - 0 1",
+                FenError::OpponentInCheck(Color::Black),
+            ),
+            (
+                "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KKkq - 0 1",
+                FenError::InvalidCastlingRights(String::from("KKkq")),
+            ),
+            (
+                "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBN1 w KQkq - 0 1",
+                FenError::InvalidCastlingRights(String::from("KQkq")),
+            ),
+            (
+                "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkz - 0 1",
+                FenError::InvalidCastlingRights(String::from("KQkz")),
+            ),
+            (
+                "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq e3 0 1",
+                FenError::InvalidEnPassant(String::from("e3")),
+            ),
+            (
+                "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq e6 0 1",
+                FenError::InvalidEnPassant(String::from("e6")),
+            ),
+            (
+                "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - x 1",
+                FenError::InvalidHalfmoveClock(String::from("x")),
+            ),
+            (
+                "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 0",
+                FenError::InvalidFullmoveNumber(String::from("0")),
+            ),
+        ];
+        for (fen, error) in cases {
+            assert_eq!(Some(error), Fen::parse(fen).err(), "{}", fen);
+        }
+    }
+}
@@ -1,42 +1,41 @@
+use crate::board_movements::{A, E, H};
+use crate::game::bitboard::{
+    bishop_attacks, rook_attacks, square_index, KING_ATTACKS, KNIGHT_ATTACKS, PAWN_ATTACKS,
+};
 use crate::game::board::Board;
 use crate::game::check_mate_status::CheckMateStatus;
+use crate::game::fen::{Fen, FenError, STARTING_POSITION};
+use crate::game::game_result::{EndReason, GameResult};
+use crate::game::movement::Movement;
+use crate::game::pgn::PgnTags;
+use crate::game::zobrist::KEYS;
 use crate::pieces::color::Color;
 use crate::pieces::piece::{PieceType, Pieces, ValidMovement};
 use crate::pieces::validator::base_validator::ContainsConflictingPiece;
 use crate::pieces::validator::row_column::{BaseLocation, RowColumn};
 use std::any::Any;
-use std::collections::VecDeque;
 use std::io;
 
-pub static ALL_MOVEMENTS: [[i8; 2]; 8] = [
-    [-1, -1],
-    [-1, 0],
-    [-1, 1],
-    [0, -1],
-    [0, 1],
-    [1, -1],
-    [1, 0],
-    [1, 1],
-];
-
 #[derive(Debug)]
 pub enum Errors {
     InvalidInput,
 }
-#[derive(Eq, PartialEq)]
+#[derive(Clone, Eq, PartialEq)]
 enum State {
     Playing,
-    Ended,
+    // the players left before the game was decided.
+    Exited,
+    Ended(GameResult),
 }
 impl State {
     fn has_ended(&self) -> bool {
         match *self {
-            State::Ended => true,
+            State::Exited | State::Ended(_) => true,
             _ => false,
         }
     }
 }
-#[derive(Eq, PartialEq, Debug)]
+#[derive(Clone, Eq, PartialEq, Debug)]
 pub struct History {
     color: Color,
     from: RowColumn,
@@ -70,52 +69,150 @@ impl History {
     }
 }
 
-pub struct HistoryOfLastFiveMovement {
-    history: VecDeque<History>,
+/// Everything a movement changed, so it can be taken back exactly.
+#[derive(Clone)]
+struct MoveRecord {
+    history: History,
+    movement: Movement,
+    // the piece as it was before moving, a promoted pawn is still a pawn here.
+    moved_piece: Pieces,
+    // where the captured piece was, only different from `to` for en passant.
+    captured: Option<(RowColumn, Pieces)>,
+    // the squares and the rook before it moved when castling.
+    castling_rook: Option<(BaseLocation, Pieces)>,
+    // the halfmove clock, draw offer and Zobrist key from before the movement.
+    halfmove_clock: u32,
+    draw_offered_by: Option<Color>,
+    hash: u64,
 }
 
-impl HistoryOfLastFiveMovement {
-    pub fn new() -> HistoryOfLastFiveMovement {
-        HistoryOfLastFiveMovement {
-            history: VecDeque::new(),
-        }
+/// Every movement of the game, plus the ones taken back so they can be played again.
+#[derive(Clone, Default)]
+pub struct MoveHistory {
+    records: Vec<MoveRecord>,
+    // the last movement taken back is the first one to redo.
+    undone: Vec<Movement>,
+    // a game set up from a FEN can start right after a pawn moved two squares.
+    before_first: Option<History>,
+}
+
+impl MoveHistory {
+    pub fn new() -> MoveHistory {
+        MoveHistory::default()
     }
 
-    pub fn add_history(&mut self, history: History) {
-        if self.history.len() >= 5 {
-            self.history.pop_front();
-        }
-        self.history.push_back(history)
+    fn add_record(&mut self, record: MoveRecord) {
+        self.records.push(record);
     }
 
+    /// The last movement played.
     pub fn peek(&self) -> Option<&History> {
-        if self.history.is_empty() {
-            return None;
+        match self.records.last() {
+            Some(record) => Some(&record.history),
+            None => self.before_first.as_ref(),
         }
-        self.history.get(self.history.len() - 1)
     }
 
     pub fn size(&self) -> usize {
-        self.history.len()
+        self.records.len()
     }
 }
 
+#[derive(Clone)]
 pub struct Game {
     board: Board,
     state: State,
     current_color: Color,
-    history: HistoryOfLastFiveMovement,
+    history: MoveHistory,
+    // the player that offered a draw, the other one can accept it on their turn.
+    draw_offered_by: Option<Color>,
+    // moves made by both players since the last capture or pawn movement.
+    halfmove_clock: u32,
+    // the Zobrist key of the current position, updated as the pieces move.
+    hash: u64,
+    // the key of every position of the game so far.
+    positions: Vec<u64>,
+    // starts at 1 and goes up after black moves.
+    fullmove_number: u32,
+    // the FEN the game started from, to save the game.
+    starting_position: String,
 }
 
 impl Game {
     pub fn new() -> Game {
         let board = Board::new();
-        Game {
+        let mut game = Game {
             board,
             state: State::Playing,
             current_color: Color::White,
-            history: HistoryOfLastFiveMovement::new(),
+            history: MoveHistory::new(),
+            draw_offered_by: None,
+            halfmove_clock: 0,
+            hash: 0,
+            positions: vec![],
+            fullmove_number: 1,
+            starting_position: String::from(STARTING_POSITION),
+        };
+        game.hash = game.compute_hash();
+        game.record_position();
+        game
+    }
+
+    /// Sets up a game from a Forsyth–Edwards Notation string, e.g.
+    /// `rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq e3 0 1`.
+    pub fn from_fen(fen: &str) -> Result<Game, FenError> {
+        let fen = Fen::parse(fen)?;
+        let mut history = MoveHistory::new();
+        // en passant looks at the last movement, so pretend the pawn just moved.
+        if let Some(en_passant) = &fen.en_passant {
+            let color = fen.current_color.opposite();
+            let (from_row, to_row) = match color {
+                Color::White => (en_passant.row + 1, en_passant.row - 1),
+                Color::Black => (en_passant.row - 1, en_passant.row + 1),
+            };
+            history.before_first = Some(History::new(
+                &color,
+                &RowColumn::new(from_row, en_passant.column),
+                &RowColumn::new(to_row, en_passant.column),
+                &PieceType::Pawn,
+            ));
         }
+        let mut game = Game {
+            board: fen.board,
+            state: State::Playing,
+            current_color: fen.current_color,
+            history,
+            draw_offered_by: None,
+            halfmove_clock: fen.halfmove_clock,
+            hash: 0,
+            positions: vec![],
+            fullmove_number: fen.fullmove_number,
+            starting_position: String::new(),
+        };
+        game.starting_position = game.to_fen();
+        game.hash = game.compute_hash();
+        game.record_position();
+        // the position could already be over, e.g. a checkmate.
+        game.check_for_game_end();
+        Ok(game)
+    }
+
+    /// The current position as a Forsyth–Edwards Notation string, the same format `from_fen`
+    /// reads.
+    pub fn to_fen(&self) -> String {
+        let en_passant = match self.get_en_passant_square() {
+            Some(square) => square.to_string(),
+            None => String::from("-"),
+        };
+        format!(
+            "{} {} {} {} {} {}",
+            self.board.get_placement(),
+            self.current_color.get_color().to_ascii_lowercase(),
+            self.get_castling_rights(),
+            en_passant,
+            self.halfmove_clock,
+            self.fullmove_number
+        )
     }
 
     fn print_help(&self) {
@@ -123,6 +220,19 @@ impl Game {
         println!("move [row_column] [row_column]: Moves a piece from an area to another.");
         println!("     e.g.: a2 a4");
         println!("     If invalid(piece missing, can't move, etc), we'll re-request your input.");
+        println!("[SAN]: Moves a piece using Standard Algebraic Notation.");
+        println!("     e.g.: Nf3, exd5, O-O, Rad1 or e8=Q");
+        println!("history: Prints every move of the game so far.");
+        println!("history [N]: Prints the last N moves, e.g.: history 10");
+        println!("perft [N]: Counts the positions N moves ahead, split by the first move.");
+        println!("undo: Takes back the last move.");
+        println!("redo: Plays the last move you took back again.");
+        println!("resign: Give up, the other player wins.");
+        println!("claim: Claim a draw once 50 moves went by without a capture or a pawn moving,");
+        println!("     or once the same position happened three times.");
+        println!("draw: Offer a draw, or accept the one the other player offered.");
+        println!("fen: Prints the current position in FEN.");
+        println!("pgn: Prints the game so far in PGN.");
         println!("exit: Exit the game.");
         println!("help: prints this.");
         println!();
@@ -210,15 +320,6 @@ impl Game {
         self.current_color = next_color;
     }
 
-    fn set_piece_to_square(&mut self, row_column: &RowColumn, piece: Pieces) {
-        self.board
-            .set_piece(row_column.row, row_column.column, piece);
-    }
-
-    fn remove_piece_from_square(&mut self, row_column: &RowColumn) {
-        self.board.remove_piece(row_column.row, row_column.column);
-    }
-
     fn is_color_square_current_user_color(&self, row: usize, column: usize) -> bool {
         if let Some(color) = self.board.squares[row][column].get_color_of_piece() {
             if self.self_is_current_player_color(&color) {
@@ -237,314 +338,570 @@ impl Game {
         }
         false
     }
-    fn get_king_position(&self) -> Option<RowColumn> {
-        for row in 0..self.board.squares.len() {
-            for column in 0..self.board.squares[0].len() {
-                if self.is_color_square_current_user_color(row, column) {
-                    continue;
-                }
-                if let Some(piece_info) = self.board.squares[row][column].get_piece_type() {
-                    match piece_info {
-                        PieceType::King => return Some(RowColumn::new(row as u8, column as u8)),
-                        _ => {
-                            // just ignore
-                        }
+    /// Squares the piece could possibly reach, without its own pieces.
+    /// The validators still have the final say, this just saves us from trying all 64 squares.
+    fn get_candidate_squares(&self, from: &RowColumn, piece: &Pieces) -> Vec<RowColumn> {
+        let bitboards = self.board.get_bitboards();
+        let square = square_index(from);
+        let occupied = bitboards.get_occupied();
+        let mut targets = match piece.get_piece_type() {
+            PieceType::Pawn => {
+                let (direction, color) = match piece.get_color() {
+                    Color::White => (-1, 0),
+                    Color::Black => (1, 1),
+                };
+                let mut pushes = 0;
+                for rows in [direction, direction * 2] {
+                    let row = from.row as i8 + rows;
+                    if (0..8).contains(&row) {
+                        pushes |= 1 << square_index(&RowColumn::new(row as u8, from.column));
                     }
                 }
+                pushes | PAWN_ATTACKS[color][square]
+            }
+            PieceType::Knight => KNIGHT_ATTACKS[square],
+            // the king can also go two squares to the side to castle.
+            PieceType::King => {
+                let mut castling = 0;
+                if from.column == E {
+                    castling = 1 << (square - 2) | 1 << (square + 2);
+                }
+                KING_ATTACKS[square] | castling
             }
+            PieceType::Bishop => bishop_attacks(square, occupied),
+            PieceType::Rook => rook_attacks(square, occupied),
+            PieceType::Queen => bishop_attacks(square, occupied) | rook_attacks(square, occupied),
+        };
+        targets &= !bitboards.get_color(piece.get_color());
+        let mut candidates = vec![];
+        while targets != 0 {
+            let target = targets.trailing_zeros() as u8;
+            candidates.push(RowColumn::new(target / 8, target % 8));
+            targets &= targets - 1;
         }
-        return None;
+        candidates
     }
 
-    fn get_movement_around(&self, row_column: &RowColumn) -> Vec<RowColumn> {
-        let mut surrounding_area = vec![];
-        for movement in ALL_MOVEMENTS {
-            let row = row_column.row as i8 + movement[0];
-            let column = row_column.column as i8 + movement[1];
-            if row < 0 || column < 0 || row >= 8 || column >= 8 {
+    /// Plays the movement on a copy of the bitboards and checks the king of `color` isn't attacked.
+    fn is_king_safe_after(
+        &self,
+        from: &RowColumn,
+        to: &RowColumn,
+        valid_movement: &ValidMovement,
+        color: &Color,
+    ) -> bool {
+        let mut bitboards = *self.board.get_bitboards();
+        // the promoted piece doesn't matter here, it blocks the same squares.
+        bitboards.apply_movement(from, to, valid_movement);
+        !bitboards.is_king_in_check(color)
+    }
+
+    /// Every legal movement of the player whose turn it is.
+    pub fn legal_moves(&self) -> Vec<Movement> {
+        let mut movements = vec![];
+        for row in 0..self.board.squares.len() {
+            for column in 0..self.board.squares[row].len() {
+                if self.is_color_square_current_user_color(row, column) {
+                    movements.append(
+                        &mut self.legal_moves_from(&RowColumn::new(row as u8, column as u8)),
+                    );
+                }
+            }
+        }
+        movements
+    }
+
+    /// Every legal movement of the piece in `square`.
+    /// Returns nothing if the square is empty or it's not that piece's turn.
+    /// A promotion shows up once per piece the pawn can become.
+    pub fn legal_moves_from(&self, square: &RowColumn) -> Vec<Movement> {
+        let mut movements = vec![];
+        let piece =
+            match self.board.squares[square.row as usize][square.column as usize].get_piece() {
+                Some(piece) if self.self_is_current_player_color(piece.get_color()) => piece,
+                _ => return movements,
+            };
+        for to in self.get_candidate_squares(square, piece) {
+            if self.is_color_square_matched(to.row as usize, to.column as usize, piece.get_color())
+            {
                 continue;
             }
-            if self.board.squares[row as usize][column as usize].has_piece() {
+            let valid_movement =
+                piece.is_valid_movement(square, &to, &self.board, self.history.peek());
+            if matches!(valid_movement, ValidMovement::INVALID)
+                || !self.is_king_safe_after(square, &to, &valid_movement, piece.get_color())
+            {
                 continue;
             }
-            surrounding_area.push(RowColumn::new(row as u8, column as u8))
+            if matches!(valid_movement, ValidMovement::Promotion) {
+                for piece_type in PieceType::PROMOTIONS {
+                    movements.push(Movement::new_promotion(
+                        square.clone(),
+                        to.clone(),
+                        piece_type,
+                    ));
+                }
+            } else {
+                movements.push(Movement::new(square.clone(), to.clone()));
+            }
         }
-        surrounding_area
+        movements
     }
 
-    fn is_check(
-        &self,
-        curr_location: &RowColumn,
-        skip_same_color: bool,
-        king_color: &Color,
-    ) -> Option<RowColumn> {
-        for row in 0..self.board.squares.len() {
-            for column in 0..self.board.squares[0].len() {
-                let is_same_color = self.is_color_square_matched(row, column, king_color);
-                if skip_same_color {
-                    if is_same_color {
-                        continue;
-                    }
-                } else {
-                    if !is_same_color {
-                        continue;
-                    }
-                }
-                if curr_location.row as usize == row && curr_location.column as usize == column {
-                    continue;
-                }
-                let row_column = RowColumn::new(row as u8, column as u8);
-                match self.board.squares[row][column].get_piece() {
-                    None => {}
-                    Some(piece_ref) => {
-                        match piece_ref.is_valid_movement_has_piece_override(
-                            &row_column,
-                            &curr_location,
-                            &self.board,
-                            self.history.peek(),
-                            // for pawn
-                            true,
-                        ) {
-                            ValidMovement::VALID
-                            | ValidMovement::CASTLING(_)
-                            | ValidMovement::EnPassant(_)
-                            | ValidMovement::Promotion => return Some(row_column),
-                            ValidMovement::INVALID => {}
-                        }
-                    }
-                }
+    /// Plays a movement, usually one returned by `legal_moves`.
+    pub fn play_movement(&mut self, movement: &Movement) -> Result<(), String> {
+        let from = &self.board.squares[movement.from.row as usize][movement.from.column as usize];
+        if let Some(color) = from.get_color_of_piece() {
+            if !self.self_is_current_player_color(&color) {
+                return Err("You can't move the other's person piece".to_string());
             }
         }
-        None
+        let next_piece = movement
+            .promotion
+            .as_ref()
+            .map(|piece_type| piece_type.get_name());
+        self.start_movement(&movement.from, &movement.to, next_piece)
+    }
+
+    /// Checks how the king of the player that's about to move is doing.
+    fn check_check_status(&self) -> CheckMateStatus {
+        let is_check = self.board.is_king_in_check(&self.current_color);
+        let has_moves = !self.legal_moves().is_empty();
+        match (is_check, has_moves) {
+            (false, true) => CheckMateStatus::NONE,
+            (true, true) => CheckMateStatus::CHECK,
+            (true, false) => CheckMateStatus::CHECKMATE,
+            (false, false) => CheckMateStatus::STALEMATE,
+        }
+    }
+
+    /// How the game ended. None while it's still being played or if the players just exited.
+    pub fn get_result(&self) -> Option<&GameResult> {
+        match &self.state {
+            State::Ended(result) => Some(result),
+            _ => None,
+        }
+    }
+
+    pub fn is_over(&self) -> bool {
+        self.state.has_ended()
+    }
+
+    /// The FEN of the position the game started from.
+    pub fn get_starting_position(&self) -> &str {
+        &self.starting_position
+    }
+
+    /// Every movement played so far, oldest first.
+    pub fn get_movements(&self) -> Vec<Movement> {
+        self.history
+            .records
+            .iter()
+            .map(|record| record.movement.clone())
+            .collect()
+    }
+
+    pub fn get_board(&self) -> &Board {
+        &self.board
+    }
+
+    pub fn get_current_color(&self) -> &Color {
+        &self.current_color
+    }
+
+    /// Starts at 1 and goes up every time black moves.
+    pub fn get_fullmove_number(&self) -> u32 {
+        self.fullmove_number
     }
 
-    fn come_to_rescue(&self, target_location: &RowColumn, king_location: &RowColumn) -> bool {
-        let movements_between = match self.board.squares[king_location.row as usize]
-            [king_location.column as usize]
+    /// Number of moves made by both players since the last capture or pawn movement.
+    pub fn get_halfmove_clock(&self) -> u32 {
+        self.halfmove_clock
+    }
+
+    /// The player whose turn it is claims a draw.
+    /// Only works if the fifty-move rule applies or the position was repeated three times.
+    pub fn claim_draw(&mut self) -> Result<(), String> {
+        if self.is_over() {
+            return Err(String::from("The game is over."));
+        }
+        if self.halfmove_clock >= 100 {
+            self.state = State::Ended(GameResult::Draw(EndReason::FiftyMoveRule));
+            return Ok(());
+        }
+        if self.get_repetition_count() >= 3 {
+            self.state = State::Ended(GameResult::Draw(EndReason::ThreefoldRepetition));
+            return Ok(());
+        }
+        Err(String::from("You can't claim a draw yet."))
+    }
+
+    /// Whether the king and rook are still on their squares and have never moved.
+    fn can_still_castle(&self, king: &RowColumn, rook: &RowColumn, color: &Color) -> bool {
+        let is_unmoved = |location: &RowColumn, piece_type: PieceType| match self.board.squares
+            [location.row as usize][location.column as usize]
             .get_piece()
         {
-            None => {
-                vec![]
+            Some(piece) => {
+                piece.get_piece_type() == piece_type
+                    && piece.get_color() == color
+                    && !piece.get_piece_info().get_has_moved()
             }
-            Some(p) => {
-                match self.board.squares[target_location.row as usize]
-                    [target_location.column as usize]
-                    .get_piece_type()
-                {
-                    None => vec![],
-                    Some(t) => match t {
-                        PieceType::Knight | PieceType::King => {
-                            vec![]
-                        }
-                        _ => p.get_movements_between(target_location, king_location),
-                    },
+            None => false,
+        };
+        is_unmoved(king, PieceType::King) && is_unmoved(rook, PieceType::Rook)
+    }
+
+    /// Who can still castle and to which side, the way FEN writes it.
+    /// e.g. `KQkq` at the start of the game, `-` if no one can castle anymore.
+    pub fn get_castling_rights(&self) -> String {
+        let mut rights = String::new();
+        for (color, row) in [(Color::White, 7), (Color::Black, 0)] {
+            let king = RowColumn::new(row, E);
+            for (rook_column, name) in [(H, 'K'), (A, 'Q')] {
+                if self.can_still_castle(&king, &RowColumn::new(row, rook_column), &color) {
+                    rights.push(match color {
+                        Color::White => name,
+                        Color::Black => name.to_ascii_lowercase(),
+                    });
                 }
             }
+        }
+        if rights.is_empty() {
+            rights.push('-');
+        }
+        rights
+    }
+
+    /// The square the last pawn skipped over if it just moved two squares.
+    pub fn get_en_passant_square(&self) -> Option<RowColumn> {
+        let last_history = self.history.peek()?;
+        let from = last_history.get_from();
+        let to = last_history.get_to();
+        if *last_history.get_piece_type() != PieceType::Pawn
+            || (from.row as i8 - to.row as i8).abs() != 2
+        {
+            return None;
+        }
+        Some(RowColumn::new((from.row + to.row) / 2, from.column))
+    }
+
+    /// The column of the en passant square, but only if the player about to move can actually
+    /// take the pawn. Otherwise it's the same position as if the pawn had moved one square at a
+    /// time.
+    fn get_capturable_en_passant_column(&self) -> Option<u8> {
+        let square = self.get_en_passant_square()?;
+        let pawn_row = match self.current_color {
+            Color::White => square.row + 1,
+            Color::Black => square.row - 1,
         };
-        for row in 0..self.board.squares.len() {
-            for column in 0..self.board.squares[0].len() {
-                if self.is_color_square_current_user_color(row, column) {
-                    continue;
-                }
-                if target_location.row as usize == row && target_location.column as usize == column
-                {
-                    continue;
-                }
-                let row_column = RowColumn::new(row as u8, column as u8);
-                match self.board.squares[row][column].get_piece() {
-                    None => {}
-                    Some(piece_ref) => {
-                        match piece_ref.get_piece_type() {
-                            PieceType::King => continue,
-                            _ => {}
-                        }
-                        // TODO: CAN IT BLOCK?
-                        match piece_ref.is_valid_movement(
-                            &row_column,
-                            &target_location,
-                            &self.board,
-                            self.history.peek(),
-                        ) {
-                            ValidMovement::VALID => return true,
-                            ValidMovement::INVALID | _ => {
-                                match piece_ref.get_piece_type() {
-                                    PieceType::King => continue,
-                                    _ => {}
-                                }
-                                for movement in &movements_between {
-                                    match piece_ref.is_valid_movement(
-                                        &row_column,
-                                        &movement,
-                                        &self.board,
-                                        self.history.peek(),
-                                    ) {
-                                        ValidMovement::VALID => return true,
-                                        ValidMovement::INVALID | _ => continue,
-                                    }
-                                }
-                            }
-                        }
-                    }
-                }
+        for column in [square.column.checked_sub(1), Some(square.column + 1)] {
+            let pawn = match column {
+                Some(column) if column < 8 => RowColumn::new(pawn_row, column),
+                _ => continue,
+            };
+            if self.board.squares[pawn.row as usize][pawn.column as usize].get_piece_type()
+                == Some(PieceType::Pawn)
+                && self
+                    .legal_moves_from(&pawn)
+                    .iter()
+                    .any(|movement| movement.to == square)
+            {
+                return Some(square.column);
             }
         }
-        false
+        None
     }
 
-    fn check_check_status(&mut self) -> CheckMateStatus {
-        if let Some(king_location) = self.get_king_position() {
-            self.print_board();
-            let surrounding_area = self.get_movement_around(&king_location);
-            let king_color = self.board.squares[king_location.row as usize]
-                [king_location.column as usize]
-                .get_color_of_piece()
-                .unwrap();
-            // is the king under fire?
-            match self.is_check(&king_location, true, &king_color) {
-                None => CheckMateStatus::NONE,
-                Some(target) => {
-                    // Is there a piece that can attack the location
-                    if self.come_to_rescue(&target, &king_location) {
-                        return CheckMateStatus::CHECK;
-                    }
-                    // we remove the king piece to pretend it's not there so we can do the check
-                    let king_piece = self.board.squares[king_location.row as usize]
-                        [king_location.column as usize]
-                        .get_actual_piece()
-                        .unwrap();
-                    for other in &surrounding_area {
-                        if self.is_color_square_current_user_color(
-                            other.row as usize,
-                            other.column as usize,
-                        ) {
-                            continue;
-                        }
-                        match self.is_check(other, true, &king_color) {
-                            None => {
-                                self.board.squares[king_location.row as usize]
-                                    [king_location.column as usize]
-                                    .set_piece(king_piece);
-                                return CheckMateStatus::CHECK;
-                            }
-                            Some(target) => {
-                                if self.come_to_rescue(&target, &other) {
-                                    self.board.squares[king_location.row as usize]
-                                        [king_location.column as usize]
-                                        .set_piece(king_piece);
-                                    return CheckMateStatus::CHECK;
-                                }
-                            }
-                        }
-                    }
+    /// Two positions are the same if the pieces are on the same squares, the same player is
+    /// about to move and both players can make the same moves.
+    fn compute_hash(&self) -> u64 {
+        KEYS.position(
+            &self.board,
+            &self.current_color,
+            &self.get_castling_rights(),
+            self.get_capturable_en_passant_column(),
+        )
+    }
 
-                    self.board.squares[king_location.row as usize][king_location.column as usize]
-                        .set_piece(king_piece);
-                    CheckMateStatus::CHECKMATE
-                }
+    /// The Zobrist key of the current position.
+    pub fn get_hash(&self) -> u64 {
+        self.hash
+    }
+
+    fn record_position(&mut self) {
+        self.positions.push(self.hash);
+    }
+
+    /// How many times the current position has happened in this game, counting this one.
+    pub fn get_repetition_count(&self) -> usize {
+        match self.positions.last() {
+            None => 0,
+            Some(current) => self
+                .positions
+                .iter()
+                .filter(|position| *position == current)
+                .count(),
+        }
+    }
+
+    /// The player whose turn it is gives up.
+    pub fn resign(&mut self) {
+        if !self.is_over() {
+            let winner = self.current_color.opposite();
+            self.state = State::Ended(GameResult::win_for(&winner, EndReason::Resignation));
+        }
+    }
+
+    /// Ends the game the way something else says it did, e.g. the result of a PGN.
+    pub(crate) fn end(&mut self, result: GameResult) {
+        self.state = State::Ended(result);
+    }
+
+    /// Both players agreed to a draw.
+    pub fn agree_to_draw(&mut self) {
+        if !self.is_over() {
+            self.state = State::Ended(GameResult::Draw(EndReason::Agreement));
+        }
+    }
+
+    /// The player whose turn it is offers a draw, or accepts the one the other player offered.
+    /// Returns true if the game ended in a draw.
+    fn offer_draw(&mut self) -> bool {
+        match &self.draw_offered_by {
+            Some(color) if *color != self.current_color => {
+                self.agree_to_draw();
+                true
+            }
+            _ => {
+                self.draw_offered_by = Some(self.current_color.clone());
+                false
             }
-        } else {
-            CheckMateStatus::NONE
         }
     }
 
     ///
     /// First, check if the from square actually has a piece.
     /// If a piece exists, we check if that piece can actually go to the square.
-    /// Finally, if valid, replace the piece.
+    /// Then, if valid, replace the piece.
+    /// Finally, if the move left the mover's own king in check, we roll the board back.
     fn start_movement(
         &mut self,
         from: &RowColumn,
         to: &RowColumn,
         next_piece: Option<char>,
     ) -> Result<(), String> {
-        let from_square = &mut self.board.squares[from.row as usize][from.column as usize];
-        let piece_type: Option<PieceType>;
-        match from_square.get_actual_piece() {
-            None => return Err(String::from("Must contain a piece")),
-            Some(mut piece) => {
-                piece_type = Some(piece.get_piece_type().clone());
-                // TODO: Check if it's actually a piece that belongs to the controlling user.
-                let is_valid =
-                    piece.is_valid_movement(&from, &to, &self.board, self.history.peek());
-                match is_valid {
-                    ValidMovement::INVALID => {
-                        self.set_piece_to_square(&from, piece);
-                        return Err(String::from("Invalid movement."));
-                    }
-                    ValidMovement::VALID => {
-                        piece.set_as_moved();
-                        self.set_piece_to_square(&to, piece);
-                    }
-                    ValidMovement::CASTLING(location) => {
-                        piece.set_as_moved();
-                        self.set_piece_to_square(&to, piece);
-                        if let Some(mut rook) = self.board.squares[location.from.row as usize]
-                            [location.from.column as usize]
-                            .get_actual_piece()
-                        {
-                            rook.set_as_moved();
-                            self.set_piece_to_square(&location.to, rook);
-                        } else {
-                            panic!("bug!! Rook should have been around!!")
-                        }
-                    }
-                    ValidMovement::EnPassant(row_column) => {
-                        self.remove_piece_from_square(&row_column);
-                        piece.set_as_moved();
-                        self.set_piece_to_square(&to, piece);
-                    }
-                    ValidMovement::Promotion => {
-                        // piece should be deleted.
-                        if let Some(user_wanted_piece) = next_piece {
-                            let some_new_piece = self.board.create_new_piece_and_set_as_moved(
-                                user_wanted_piece,
-                                matches!(self.current_color, Color::Black),
-                            );
-                            match some_new_piece {
-                                None => {
-                                    self.set_piece_to_square(&from, piece);
-                                    return Err(String::from(
-                                        "Invalid piece. Please enter Q, R, B, or N",
-                                    ));
-                                }
-                                Some(new_piece) => {
-                                    // TODO: I don' think i need to call the drop fun
-                                    // drop(piece);
-                                    self.set_piece_to_square(&to, new_piece);
-                                }
-                            }
-                        } else {
-                            self.set_piece_to_square(&from, piece);
-                            return Err(String::from("We need to know what piece you want"));
+        if self.is_over() {
+            return Err(String::from("The game is over."));
+        }
+        self.make_movement(from, to, next_piece)?;
+        self.check_for_game_end();
+        Ok(())
+    }
+
+    /// Plays a legal movement without checking if it ended the game, for looking ahead where
+    /// only the moves matter, like perft or the engine.
+    pub(crate) fn make_legal_movement(&mut self, movement: &Movement) {
+        let next_piece = movement
+            .promotion
+            .as_ref()
+            .map(|piece_type| piece_type.get_name());
+        self.make_movement(&movement.from, &movement.to, next_piece)
+            .expect("legal movements can always be played");
+    }
+
+    fn make_movement(
+        &mut self,
+        from: &RowColumn,
+        to: &RowColumn,
+        next_piece: Option<char>,
+    ) -> Result<(), String> {
+        let (moved_piece, valid_movement) =
+            match self.board.squares[from.row as usize][from.column as usize].get_piece() {
+                None => return Err(String::from("Must contain a piece")),
+                Some(piece) => (
+                    piece.clone(),
+                    piece.is_valid_movement(from, to, &self.board, self.history.peek()),
+                ),
+            };
+        let piece_type = moved_piece.get_piece_type();
+        let color = moved_piece.get_color().clone();
+        if self.is_color_square_matched(to.row as usize, to.column as usize, &color) {
+            return Err(String::from("You can't capture your own piece."));
+        }
+        let captured_location = match &valid_movement {
+            ValidMovement::EnPassant(location) => location.clone(),
+            _ => to.clone(),
+        };
+        let captured = self.board.squares[captured_location.row as usize]
+            [captured_location.column as usize]
+            .get_piece()
+            .clone()
+            .map(|piece| (captured_location, piece));
+        let castling_rook = match &valid_movement {
+            ValidMovement::CASTLING(location) => self.board.squares[location.from.row as usize]
+                [location.from.column as usize]
+                .get_piece()
+                .clone()
+                .map(|rook| (location.clone(), rook)),
+            _ => None,
+        };
+        let promoted_piece = match valid_movement {
+            ValidMovement::INVALID => return Err(String::from("Invalid movement.")),
+            ValidMovement::Promotion => match next_piece {
+                None => return Err(String::from("We need to know what piece you want")),
+                Some(user_wanted_piece) => {
+                    match self.board.create_new_piece_and_set_as_moved(
+                        user_wanted_piece,
+                        matches!(color, Color::Black),
+                    ) {
+                        None => {
+                            return Err(String::from("Invalid piece. Please enter Q, R, B, or N"))
                         }
+                        some_new_piece => some_new_piece,
                     }
-                };
-            }
+                }
+            },
+            _ => None,
+        };
+        let promotion = promoted_piece.as_ref().map(|piece| piece.get_piece_type());
+        // what the key had before the movement goes away, the rest is added back at the end.
+        let mut hash = self.hash
+            ^ KEYS.side_to_move(&self.current_color)
+            ^ KEYS.castling_rights(&self.get_castling_rights())
+            ^ KEYS.en_passant(self.get_capturable_en_passant_column());
+        // keep a copy around in case the move turns out to be illegal.
+        let board_before_movement = self.board.clone();
+        self.board
+            .apply_movement(from, to, &valid_movement, promoted_piece);
+        if self.board.is_king_in_check(&color) {
+            self.board = board_before_movement;
+            return Err(String::from(
+                "Illegal movement. Your king would be in check.",
+            ));
+        }
+        let is_capture = captured.is_some();
+        hash ^= KEYS.piece(&moved_piece, from);
+        if let Some(piece) = self.board.squares[to.row as usize][to.column as usize].get_piece() {
+            hash ^= KEYS.piece(piece, to);
+        }
+        if let Some((location, piece)) = &captured {
+            hash ^= KEYS.piece(piece, location);
+        }
+        if let Some((location, rook)) = &castling_rook {
+            hash ^= KEYS.piece(rook, &location.from) ^ KEYS.piece(rook, &location.to);
         }
         // add the history.
-        self.history.add_history(History::new(
-            &self.current_color,
-            from,
-            to,
-            &piece_type.unwrap(),
-        ));
+        self.history.add_record(MoveRecord {
+            history: History::new(&color, from, to, &piece_type),
+            movement: Movement {
+                from: from.clone(),
+                to: to.clone(),
+                promotion,
+            },
+            moved_piece,
+            captured,
+            castling_rook,
+            halfmove_clock: self.halfmove_clock,
+            draw_offered_by: self.draw_offered_by.clone(),
+            hash: self.hash,
+        });
+        // a new movement replaces whatever was taken back.
+        self.history.undone.clear();
+        if is_capture || piece_type == PieceType::Pawn {
+            self.halfmove_clock = 0;
+        } else {
+            self.halfmove_clock += 1;
+        }
+        if matches!(color, Color::Black) {
+            self.fullmove_number += 1;
+        }
+        self.set_next_player_color();
+        // an offer that wasn't accepted goes away once the other player moves.
+        if self.draw_offered_by.as_ref() == Some(&self.current_color) {
+            self.draw_offered_by = None;
+        }
+        self.hash = hash
+            ^ KEYS.side_to_move(&self.current_color)
+            ^ KEYS.castling_rights(&self.get_castling_rights())
+            ^ KEYS.en_passant(self.get_capturable_en_passant_column());
+        self.record_position();
+        Ok(())
+    }
+
+    /// Takes back the last movement, even if it ended the game.
+    pub fn undo(&mut self) -> Result<(), String> {
+        let record = match self.history.records.pop() {
+            None => return Err(String::from("There's nothing to undo.")),
+            Some(record) => record,
+        };
+        let from = &record.movement.from;
+        let to = &record.movement.to;
+        self.board.take_piece(to.row, to.column);
+        if let Some((location, rook)) = record.castling_rook {
+            self.board.take_piece(location.to.row, location.to.column);
+            self.board
+                .set_piece(location.from.row, location.from.column, rook);
+        }
+        self.board
+            .set_piece(from.row, from.column, record.moved_piece);
+        if let Some((location, captured)) = record.captured {
+            self.board
+                .set_piece(location.row, location.column, captured);
+        }
+        self.current_color = record.history.color;
+        if matches!(self.current_color, Color::Black) {
+            self.fullmove_number -= 1;
+        }
+        self.halfmove_clock = record.halfmove_clock;
+        self.draw_offered_by = record.draw_offered_by;
+        self.hash = record.hash;
+        self.positions.pop();
+        self.state = State::Playing;
+        self.history.undone.push(record.movement);
+        Ok(())
+    }
+
+    /// Plays the last movement that was taken back again.
+    pub fn redo(&mut self) -> Result<(), String> {
+        let movement = match self.history.undone.pop() {
+            None => return Err(String::from("There's nothing to redo.")),
+            Some(movement) => movement,
+        };
+        // playing it clears what's left to redo, so keep it around.
+        let undone = std::mem::take(&mut self.history.undone);
+        self.play_movement(&movement)?;
+        self.history.undone = undone;
+        Ok(())
+    }
+
+    /// Ends the game if the player that's about to move is checkmated or there's a draw.
+    fn check_for_game_end(&mut self) {
         match self.check_check_status() {
-            CheckMateStatus::NONE => {
+            CheckMateStatus::NONE | CheckMateStatus::CHECK => {
                 // do nothing
             }
-            CheckMateStatus::CHECK => {
-                println!("Check!")
-            }
             CheckMateStatus::CHECKMATE => {
-                println!("Checkmate! {} wins!", self.current_color.get_color());
-                self.state = State::Ended;
+                let winner = self.current_color.opposite();
+                self.state = State::Ended(GameResult::win_for(&winner, EndReason::Checkmate));
+            }
+            CheckMateStatus::STALEMATE => {
+                self.state = State::Ended(GameResult::Draw(EndReason::Stalemate));
             }
         }
-        self.set_next_player_color();
-        Ok(())
+        // a checkmate on the last move still counts.
+        if !self.is_over() && self.halfmove_clock >= 150 {
+            self.state = State::Ended(GameResult::Draw(EndReason::SeventyFiveMoveRule));
+        }
+        if !self.is_over() && self.get_repetition_count() >= 5 {
+            self.state = State::Ended(GameResult::Draw(EndReason::FivefoldRepetition));
+        }
+        if !self.is_over() && self.board.has_insufficient_material() {
+            self.state = State::Ended(GameResult::Draw(EndReason::InsufficientMaterial));
+        }
     }
 
-    fn get_current_user_color(&self) -> String {
+    pub(crate) fn get_current_user_color(&self) -> String {
         return self.current_color.get_color();
     }
 
@@ -634,12 +991,7 @@ impl Game {
                             next_piece_optional_char,
                         ) {
                             Ok(_) => {
-                                if self.state == State::Playing {
-                                    println!(
-                                        "{}, it's your turn now",
-                                        self.get_current_user_color()
-                                    );
-                                }
+                                self.print_status();
                                 Ok(())
                             }
                             Err(error) => Err(error),
@@ -651,15 +1003,112 @@ impl Game {
                     self.print_help();
                     Ok(())
                 }
+                "resign" => {
+                    self.resign();
+                    self.print_status();
+                    Ok(())
+                }
+                "claim" => {
+                    self.claim_draw()?;
+                    self.print_status();
+                    Ok(())
+                }
+                "draw" => {
+                    if self.is_over() {
+                        return Err(String::from("The game is over."));
+                    }
+                    if self.offer_draw() {
+                        self.print_status();
+                    } else {
+                        println!(
+                            "{} offers a draw. Type in draw on your turn to accept it.",
+                            self.get_current_user_color()
+                        );
+                    }
+                    Ok(())
+                }
+                "history" => {
+                    let plies = match splitted.next() {
+                        None => None,
+                        Some(plies) => match plies.parse::<usize>() {
+                            Ok(plies) => Some(plies),
+                            Err(_) => {
+                                return Err(String::from(
+                                    "Invalid number. e.g. history 10 for the last 10 moves.",
+                                ))
+                            }
+                        },
+                    };
+                    self.print_history(plies);
+                    Ok(())
+                }
+                "perft" => {
+                    let depth = match splitted.next().map(|depth| depth.parse::<u32>()) {
+                        Some(Ok(depth)) => depth,
+                        _ => return Err(String::from("Invalid depth. e.g. perft 3")),
+                    };
+                    let mut nodes = 0;
+                    for (movement, count) in self.divide(depth) {
+                        println!("{}: {}", movement, count);
+                        nodes += count;
+                    }
+                    println!("Nodes searched: {}", nodes);
+                    Ok(())
+                }
+                "undo" => {
+                    self.undo()?;
+                    self.print_status();
+                    Ok(())
+                }
+                "redo" => {
+                    self.redo()?;
+                    self.print_status();
+                    Ok(())
+                }
+                "fen" => {
+                    println!("{}", self.to_fen());
+                    Ok(())
+                }
+                "pgn" => self
+                    .write_pgn(&mut io::stdout(), &PgnTags::default())
+                    .map_err(|error| error.to_string()),
                 "exit" => {
-                    self.state = State::Ended;
+                    self.state = State::Exited;
+                    Ok(())
+                }
+                // anything else has to be a move in SAN, e.g. Nf3.
+                san => {
+                    let movement = self.parse_san(san)?;
+                    self.play_movement(&movement)?;
+                    self.print_status();
                     Ok(())
                 }
-                _ => Err(format!("Unknown input {}", action)),
             },
         }
     }
 
+    fn print_history(&self, plies: Option<usize>) {
+        let movements = self.get_numbered_movements(plies);
+        if movements.is_empty() {
+            println!("No moves yet.");
+        } else {
+            println!("{}", movements.join(" "));
+        }
+    }
+
+    /// Lets the players know how the game is going after something happened.
+    pub(crate) fn print_status(&self) {
+        match &self.state {
+            State::Ended(result) => println!("{}", result),
+            _ => {
+                if self.board.is_king_in_check(&self.current_color) {
+                    println!("Check!")
+                }
+                println!("{}, it's your turn now", self.get_current_user_color());
+            }
+        }
+    }
+
     pub fn set_piece(&mut self, row: u8, column: u8, piece: Pieces) {
         self.board.set_piece(row, column, piece);
     }
@@ -701,7 +1150,10 @@ impl Game {
 mod tests {
 
     use crate::game::board::Board;
-    use crate::game::game::{Game, History, HistoryOfLastFiveMovement, State};
+    use crate::game::fen::STARTING_POSITION;
+    use crate::game::game::{Game, History, MoveHistory, State};
+    use crate::game::game_result::{EndReason, GameResult};
+    use crate::game::movement::Movement;
     use crate::pieces::color::Color;
     use crate::pieces::piece::{PieceInfo, PieceType, Pieces};
     use crate::pieces::validator::row_column::RowColumn;
@@ -713,7 +1165,13 @@ mod tests {
                 board,
                 state: State::Playing,
                 current_color: Color::White,
-                history: HistoryOfLastFiveMovement::new(),
+                history: MoveHistory::new(),
+                draw_offered_by: None,
+                halfmove_clock: 0,
+                hash: 0,
+                positions: vec![],
+                fullmove_number: 1,
+                starting_position: String::new(),
             }
         }
 
@@ -722,7 +1180,13 @@ mod tests {
                 board,
                 state: State::Playing,
                 current_color: Color::White,
-                history: HistoryOfLastFiveMovement::new(),
+                history: MoveHistory::new(),
+                draw_offered_by: None,
+                halfmove_clock: 0,
+                hash: 0,
+                positions: vec![],
+                fullmove_number: 1,
+                starting_position: String::new(),
             }
         }
     }
@@ -1241,36 +1705,143 @@ mod tests {
         game.print_board();
     }
 
-    // BLACK PIECES
-    #[test]
-    fn black_attempts_to_castle_e8_to_g8() {
-        // black king is at e1.
-        // black rook is at a1 and h1
-        // black should be able to perform a castling
-        // black king will perform e1 to g1
+    fn set_up_white_castling(attacker_square: &str) -> Game {
         let board = Board::create_empty_board();
         let mut game = Game::inject_board(board);
-        let from = get_row_column(&game, String::from("e8"));
-        let to = get_row_column(&game, String::from("g8"));
-        let black_rook_placement = get_row_column(&game, String::from("h8"));
-        let black_king = PieceType::King.create_actual_piece(PieceInfo::new(Color::Black, true));
-
+        let white_king = PieceType::King.create_actual_piece(PieceInfo::new(Color::White, true));
+        let white_rook_a1 = PieceType::Rook.create_actual_piece(PieceInfo::new(Color::White, true));
+        let white_rook_h1 = PieceType::Rook.create_actual_piece(PieceInfo::new(Color::White, true));
         let black_rook = PieceType::Rook.create_actual_piece(PieceInfo::new(Color::Black, true));
+        game.set_piece_row_col(&get_row_column(&game, String::from("e1")), white_king);
+        game.set_piece_row_col(&get_row_column(&game, String::from("a1")), white_rook_a1);
+        game.set_piece_row_col(&get_row_column(&game, String::from("h1")), white_rook_h1);
+        game.set_piece_row_col(
+            &get_row_column(&game, String::from(attacker_square)),
+            black_rook,
+        );
+        game
+    }
 
-        game.set_piece_row_col(&from, black_king);
-        game.set_piece_row_col(&black_rook_placement, black_rook);
-
-        let result = game.start_movement(&from, &to, None);
+    #[test]
+    fn white_attempts_to_castle_e1_g1_while_in_check() {
+        // Let's imagine this
+        //    a b c d e f g h
+        // 8   ##  ##BR##  ## 8
+        // 7 ##  ##  ##  ##   7
+        // 6   ##  ##  ##  ## 6
+        // 5 ##  ##  ##  ##   5
+        // 4   ##  ##  ##  ## 4
+        // 3 ##  ##  ##  ##   3
+        // 2   ##  ##  ##  ## 2
+        // 1 WR  ##  WK  ##WR1
+        //    a b c d e f g h
+        // the king is in check so it can't castle to either side.
+        let mut game = set_up_white_castling("e8");
+        let from = get_row_column(&game, String::from("e1"));
 
-        assert_eq!(true, result.is_ok());
-        game.print_board()
+        let result = game.start_movement(&from, &get_row_column(&game, String::from("g1")), None);
+        assert_eq!(true, result.is_err());
+        let result = game.start_movement(&from, &get_row_column(&game, String::from("c1")), None);
+        assert_eq!(true, result.is_err());
     }
 
     #[test]
-    fn black_attempts_to_castle_e1_c1() {
-        // black king is at e1.
-        // black rook is at a1 and h1
-        // black should be able to perform a castling
+    fn white_attempts_to_castle_e1_g1_through_attacked_f1() {
+        // Let's imagine this
+        //    a b c d e f g h
+        // 8   ##  ##  BR  ## 8
+        // 7 ##  ##  ##  ##   7
+        // 6   ##  ##  ##  ## 6
+        // 5 ##  ##  ##  ##   5
+        // 4   ##  ##  ##  ## 4
+        // 3 ##  ##  ##  ##   3
+        // 2   ##  ##  ##  ## 2
+        // 1 WR  ##  WK  ##WR1
+        //    a b c d e f g h
+        // the king would pass through f1 which the black rook attacks.
+        let mut game = set_up_white_castling("f8");
+        let from = get_row_column(&game, String::from("e1"));
+
+        let result = game.start_movement(&from, &get_row_column(&game, String::from("g1")), None);
+        assert_eq!(true, result.is_err());
+        // the other side is fine.
+        let result = game.start_movement(&from, &get_row_column(&game, String::from("c1")), None);
+        assert_eq!(true, result.is_ok());
+    }
+
+    #[test]
+    fn white_attempts_to_castle_e1_g1_into_check() {
+        let mut game = set_up_white_castling("g8");
+        let from = get_row_column(&game, String::from("e1"));
+
+        let result = game.start_movement(&from, &get_row_column(&game, String::from("g1")), None);
+        assert_eq!(true, result.is_err());
+        assert_eq!(
+            false,
+            game.legal_moves().contains(&Movement::new(
+                from,
+                get_row_column(&game, String::from("g1"))
+            ))
+        );
+    }
+
+    #[test]
+    fn white_attempts_to_castle_e1_c1_rook_passes_attacked_b1() {
+        // only the squares the king walks through matter, the rook can pass b1.
+        let mut game = set_up_white_castling("b8");
+        let from = get_row_column(&game, String::from("e1"));
+
+        let result = game.start_movement(&from, &get_row_column(&game, String::from("c1")), None);
+        assert_eq!(true, result.is_ok());
+    }
+
+    #[test]
+    fn white_attempts_to_castle_e1_g1_with_black_rook() {
+        let board = Board::create_empty_board();
+        let mut game = Game::inject_board(board);
+        let white_king = PieceType::King.create_actual_piece(PieceInfo::new(Color::White, true));
+        let black_rook = PieceType::Rook.create_actual_piece(PieceInfo::new(Color::Black, true));
+        game.set_piece_row_col(&get_row_column(&game, String::from("e1")), white_king);
+        game.set_piece_row_col(&get_row_column(&game, String::from("h1")), black_rook);
+
+        let result = game.start_movement(
+            &get_row_column(&game, String::from("e1")),
+            &get_row_column(&game, String::from("g1")),
+            None,
+        );
+        assert_eq!(true, result.is_err());
+    }
+
+    // BLACK PIECES
+    #[test]
+    fn black_attempts_to_castle_e8_to_g8() {
+        // black king is at e1.
+        // black rook is at a1 and h1
+        // black should be able to perform a castling
+        // black king will perform e1 to g1
+        let board = Board::create_empty_board();
+        let mut game = Game::inject_board(board);
+        let from = get_row_column(&game, String::from("e8"));
+        let to = get_row_column(&game, String::from("g8"));
+        let black_rook_placement = get_row_column(&game, String::from("h8"));
+        let black_king = PieceType::King.create_actual_piece(PieceInfo::new(Color::Black, true));
+
+        let black_rook = PieceType::Rook.create_actual_piece(PieceInfo::new(Color::Black, true));
+
+        game.set_piece_row_col(&from, black_king);
+        game.set_piece_row_col(&black_rook_placement, black_rook);
+
+        let result = game.start_movement(&from, &to, None);
+
+        assert_eq!(true, result.is_ok());
+        game.print_board()
+    }
+
+    #[test]
+    fn black_attempts_to_castle_e1_c1() {
+        // black king is at e1.
+        // black rook is at a1 and h1
+        // black should be able to perform a castling
         // black king will perform e1 to g1
         let board = Board::create_empty_board();
         let mut game = Game::inject_board(board);
@@ -1395,28 +1966,30 @@ mod tests {
 
     #[test]
     fn assure_last_history_working_great() {
-        let mut histories = HistoryOfLastFiveMovement::new();
-        assert_eq!(histories.peek(), None);
+        let mut game = Game::new();
+        assert_eq!(game.history.peek(), None);
+        let inputs = [
+            "move g1 f3",
+            "move g8 f6",
+            "move f3 g1",
+            "move f6 g8",
+            "move b1 c3",
+            "move b8 c6",
+        ];
+        for (i, input) in inputs.iter().enumerate() {
+            game.handle_input(input).unwrap();
+            assert_eq!(game.history.size(), i + 1);
+        }
         let color = Color::Black;
-        let piece_type = &PieceType::Pawn;
-        for i in 1..=5 {
-            let from = RowColumn::new(i, 0);
-            let history = History::new(&color, &from, &from, piece_type);
-            histories.add_history(history);
-            assert_eq!(
-                histories.peek(),
-                Some(&History::new(&color, &from, &from, piece_type))
-            );
-            assert_eq!(histories.size(), i as usize)
-        }
-        let from = RowColumn::new(6, 0);
-        let history = History::new(&color, &from, &from, piece_type);
-        histories.add_history(history);
+        let from = get_row_column(&game, String::from("b8"));
+        let to = get_row_column(&game, String::from("c6"));
         assert_eq!(
-            histories.peek(),
-            Some(&History::new(&color, &from, &from, piece_type))
+            game.history.peek(),
+            Some(&History::new(&color, &from, &to, &PieceType::Knight))
         );
-        assert_eq!(histories.size(), 5)
+        // nothing is forgotten.
+        assert_eq!(6, game.get_movements().len());
+        assert_eq!(MoveHistory::new().size(), 0);
     }
 
     struct TestInfo {
@@ -1914,4 +2487,673 @@ mod tests {
 
         assert_eq!(true, result.is_err());
     }
+
+    #[test]
+    fn pinned_bishop_cant_leave_the_pin() {
+        // Let's imagine this
+        //    a b c d e f g h
+        // 8   ##  ##  ##  ## 8
+        // 7 ##  ##  ##  ##   7
+        // 6   ##  ##  ##  ## 6
+        // 5 ##  ##  ##  ##   5
+        // 4   BB  ##  ##  ## 4
+        // 3 ##  ##  ##  ##   3
+        // 2   ##  WB  ##  ## 2
+        // 1 ##  ##  WK  ##   1
+        //    a b c d e f g h
+        // the white bishop is pinned by the black bishop.
+        // it can move along the pin but not off of it.
+        let board = Board::create_empty_board();
+        let mut game = Game::inject_board(board);
+        let white_king = PieceType::King.create_actual_piece(PieceInfo::new(Color::White, true));
+        let white_bishop =
+            PieceType::Bishop.create_actual_piece(PieceInfo::new(Color::White, false));
+        let black_bishop =
+            PieceType::Bishop.create_actual_piece(PieceInfo::new(Color::Black, false));
+        game.set_piece_row_col(&get_row_column(&game, String::from("e1")), white_king);
+        game.set_piece_row_col(&get_row_column(&game, String::from("d2")), white_bishop);
+        game.set_piece_row_col(&get_row_column(&game, String::from("b4")), black_bishop);
+
+        let from = get_row_column(&game, String::from("d2"));
+        let result = game.start_movement(&from, &get_row_column(&game, String::from("e3")), None);
+        assert_eq!(
+            Err(String::from(
+                "Illegal movement. Your king would be in check."
+            )),
+            result
+        );
+        // the board must be untouched after rejecting the movement.
+        assert_eq!(
+            Some(PieceType::Bishop),
+            game.board.squares[from.row as usize][from.column as usize].get_piece_type()
+        );
+
+        let result = game.start_movement(&from, &get_row_column(&game, String::from("c3")), None);
+        assert_eq!(true, result.is_ok());
+    }
+
+    #[test]
+    fn king_cant_walk_into_an_attacked_square() {
+        // Let's imagine this
+        //    a b c d e f g h
+        // 8   ##  BR  ##  ## 8
+        // 7 ##  ##  ##  ##   7
+        // 6   ##  ##  ##  ## 6
+        // 5 ##  ##  ##  ##   5
+        // 4   ##  ##  ##  ## 4
+        // 3 ##  ##  ##  ##   3
+        // 2   ##  ##  ##  ## 2
+        // 1 ##  ##  WK  ##   1
+        //    a b c d e f g h
+        // d1 is covered by the black rook, f1 isn't.
+        let board = Board::create_empty_board();
+        let mut game = Game::inject_board(board);
+        let white_king = PieceType::King.create_actual_piece(PieceInfo::new(Color::White, true));
+        let black_rook = PieceType::Rook.create_actual_piece(PieceInfo::new(Color::Black, true));
+        game.set_piece_row_col(&get_row_column(&game, String::from("e1")), white_king);
+        game.set_piece_row_col(&get_row_column(&game, String::from("d8")), black_rook);
+
+        let from = get_row_column(&game, String::from("e1"));
+        let result = game.start_movement(&from, &get_row_column(&game, String::from("d1")), None);
+        assert_eq!(true, result.is_err());
+
+        let result = game.start_movement(&from, &get_row_column(&game, String::from("f1")), None);
+        assert_eq!(true, result.is_ok());
+    }
+
+    #[test]
+    fn cant_capture_own_piece() {
+        let board = Board::create_empty_board();
+        let mut game = Game::inject_board(board);
+        let white_rook = PieceType::Rook.create_actual_piece(PieceInfo::new(Color::White, true));
+        let white_pawn = PieceType::Pawn.create_actual_piece(PieceInfo::new(Color::White, true));
+        game.set_piece_row_col(&get_row_column(&game, String::from("a1")), white_rook);
+        game.set_piece_row_col(&get_row_column(&game, String::from("a2")), white_pawn);
+
+        let result = game.start_movement(
+            &get_row_column(&game, String::from("a1")),
+            &get_row_column(&game, String::from("a2")),
+            None,
+        );
+        assert_eq!(
+            Err(String::from("You can't capture your own piece.")),
+            result
+        );
+    }
+
+    #[test]
+    fn white_pawn_cant_capture_backwards() {
+        let board = Board::create_empty_board();
+        let mut game = Game::inject_board(board);
+        let white_pawn = PieceType::Pawn.create_actual_piece(PieceInfo::new(Color::White, true));
+        let black_pawn = PieceType::Pawn.create_actual_piece(PieceInfo::new(Color::Black, true));
+        game.set_piece_row_col(&get_row_column(&game, String::from("e4")), white_pawn);
+        game.set_piece_row_col(&get_row_column(&game, String::from("d3")), black_pawn);
+
+        let result = game.start_movement(
+            &get_row_column(&game, String::from("e4")),
+            &get_row_column(&game, String::from("d3")),
+            None,
+        );
+        assert_eq!(true, result.is_err());
+    }
+    #[test]
+    fn legal_moves_at_the_start() {
+        let game = Game::new();
+        assert_eq!(20, game.legal_moves().len());
+
+        let knight_movements = game.legal_moves_from(&get_row_column(&game, String::from("b1")));
+        assert_eq!(
+            vec![
+                Movement::new(
+                    get_row_column(&game, String::from("b1")),
+                    get_row_column(&game, String::from("a3"))
+                ),
+                Movement::new(
+                    get_row_column(&game, String::from("b1")),
+                    get_row_column(&game, String::from("c3"))
+                ),
+            ],
+            knight_movements
+        );
+        // not black's turn yet
+        assert_eq!(
+            true,
+            game.legal_moves_from(&get_row_column(&game, String::from("b8")))
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn legal_moves_pawn_cant_jump_over_a_piece() {
+        let mut game = Game::new();
+        let black_knight =
+            PieceType::Knight.create_actual_piece(PieceInfo::new(Color::Black, false));
+        game.set_piece_row_col(&get_row_column(&game, String::from("e3")), black_knight);
+
+        let pawn_movements = game.legal_moves_from(&get_row_column(&game, String::from("e2")));
+        assert_eq!(true, pawn_movements.is_empty());
+
+        let result = game.handle_input("move e2 e4");
+        assert_eq!(true, result.is_err());
+    }
+
+    #[test]
+    fn legal_moves_promotion_castling_and_en_passant() {
+        // Let's imagine this
+        //    a b c d e f g h
+        // 8   ##  ##BK##  ## 8
+        // 7 WP  ##  ##  ##   7
+        // 6   ##  ##  ##  ## 6
+        // 5 ##  ##WP##  ##   5
+        // 4   ##  ##  ##  ## 4
+        // 3 ##  ##  ##  ##   3
+        // 2   ##  ##  ##  ## 2
+        // 1 ##  ##  WK  ##WR1
+        //    a b c d e f g h
+        // and black plays e7 e5 right before white's turn.
+        let board = Board::create_empty_board();
+        let mut game = Game::inject_board(board);
+        let pieces = [
+            ("a7", PieceType::Pawn, Color::White),
+            ("d5", PieceType::Pawn, Color::White),
+            ("e1", PieceType::King, Color::White),
+            ("h1", PieceType::Rook, Color::White),
+            ("e7", PieceType::Pawn, Color::Black),
+            ("e8", PieceType::King, Color::Black),
+        ];
+        for (square, piece_type, color) in pieces {
+            let mut piece = piece_type.create_actual_piece(PieceInfo::new(color, true));
+            if square == "a7" || square == "d5" {
+                piece.set_as_moved();
+            }
+            game.set_piece_row_col(&get_row_column(&game, String::from(square)), piece);
+        }
+        game.set_next_player_color();
+        game.handle_input("move e7 e5").unwrap();
+
+        let movements = game.legal_moves();
+        let promotions: Vec<&Movement> = movements
+            .iter()
+            .filter(|movement| movement.promotion.is_some())
+            .collect();
+        assert_eq!(4, promotions.len());
+
+        let castling = Movement::new(
+            get_row_column(&game, String::from("e1")),
+            get_row_column(&game, String::from("g1")),
+        );
+        assert_eq!(true, movements.contains(&castling));
+
+        let en_passant = Movement::new(
+            get_row_column(&game, String::from("d5")),
+            get_row_column(&game, String::from("e6")),
+        );
+        assert_eq!(true, movements.contains(&en_passant));
+        game.play_movement(&en_passant).unwrap();
+        let passed_pawn = get_row_column(&game, String::from("e5"));
+        assert_eq!(
+            false,
+            game.board.squares[passed_pawn.row as usize][passed_pawn.column as usize].has_piece()
+        );
+    }
+
+    #[test]
+    fn play_movement_only_moves_the_current_player_pieces() {
+        let mut game = Game::new();
+        let e7e5 = Movement::new(
+            get_row_column(&game, String::from("e7")),
+            get_row_column(&game, String::from("e5")),
+        );
+        let fen = game.to_fen();
+        let hash = game.get_hash();
+        assert_eq!(true, game.play_movement(&e7e5).is_err());
+        assert_eq!(fen, game.to_fen());
+        assert_eq!(hash, game.get_hash());
+        assert_eq!(Color::White, *game.get_current_color());
+    }
+
+    #[test]
+    fn en_passant_only_after_moving_two_squares() {
+        let board = Board::create_empty_board();
+        let mut game = Game::inject_board(board);
+        let white_pawn = PieceType::Pawn.create_actual_piece(PieceInfo::new(Color::White, true));
+        let black_pawn = PieceType::Pawn.create_actual_piece(PieceInfo::new(Color::Black, true));
+        game.set_piece_row_col(&get_row_column(&game, String::from("d5")), white_pawn);
+        game.set_piece_row_col(&get_row_column(&game, String::from("e6")), black_pawn);
+        game.set_next_player_color();
+        game.handle_input("move e6 e5").unwrap();
+
+        let result = game.handle_input("move d5 e6");
+        assert_eq!(true, result.is_err());
+    }
+    #[test]
+    fn stalemate_is_a_draw() {
+        // Let's imagine this
+        //    a b c d e f g h
+        // 8   ##  ##  ##  BK 8
+        // 7 ##  ##  ##  WK   7
+        // 6   ##  ##  ##  ## 6
+        // 5 ##  ##  ##  ##WQ 5
+        // 4   ##  ##  ##  ## 4
+        // 3 ##  ##  ##  ##   3
+        // 2   ##  ##  ##  ## 2
+        // 1 ##  ##  ##  ##   1
+        //    a b c d e f g h
+        // white plays g5 g6, black isn't in check but can't move anywhere.
+        let board = Board::create_empty_board();
+        let mut game = Game::inject_board(board);
+        let pieces = [
+            ("h8", PieceType::King, Color::Black),
+            ("f7", PieceType::King, Color::White),
+            ("g5", PieceType::Queen, Color::White),
+        ];
+        for (square, piece_type, color) in pieces {
+            let piece = piece_type.create_actual_piece(PieceInfo::new(color, true));
+            game.set_piece_row_col(&get_row_column(&game, String::from(square)), piece);
+        }
+
+        game.handle_input("move g5 g6").unwrap();
+
+        assert_eq!(
+            Some(&GameResult::Draw(EndReason::Stalemate)),
+            game.get_result()
+        );
+        let result = game.handle_input("move h8 g8");
+        assert_eq!(Err(String::from("The game is over.")), result);
+    }
+
+    #[test]
+    fn checkmate_result() {
+        let mut game = Game::new();
+        for input in ["move f2 f3", "move e7 e5", "move g2 g4", "move d8 h4"] {
+            game.handle_input(input).unwrap();
+        }
+        assert_eq!(
+            Some(&GameResult::BlackWins(EndReason::Checkmate)),
+            game.get_result()
+        );
+        assert_eq!(Some(Color::Black), game.get_result().unwrap().get_winner());
+    }
+
+    #[test]
+    fn resigning_and_agreeing_to_a_draw() {
+        let mut game = Game::new();
+        game.handle_input("resign").unwrap();
+        assert_eq!(
+            Some(&GameResult::BlackWins(EndReason::Resignation)),
+            game.get_result()
+        );
+
+        let mut game = Game::new();
+        game.handle_input("draw").unwrap();
+        assert_eq!(None, game.get_result());
+        // white can't accept its own offer
+        game.handle_input("draw").unwrap();
+        assert_eq!(None, game.get_result());
+        game.handle_input("move e2 e4").unwrap();
+        game.handle_input("draw").unwrap();
+        assert_eq!(
+            Some(&GameResult::Draw(EndReason::Agreement)),
+            game.get_result()
+        );
+    }
+
+    #[test]
+    fn draw_offer_goes_away_after_a_move() {
+        let mut game = Game::new();
+        game.handle_input("draw").unwrap();
+        game.handle_input("move e2 e4").unwrap();
+        game.handle_input("move e7 e5").unwrap();
+        game.handle_input("move g1 f3").unwrap();
+        // black declined by moving, this is a new offer.
+        game.handle_input("draw").unwrap();
+        assert_eq!(None, game.get_result());
+    }
+
+    #[test]
+    fn exiting_has_no_result() {
+        let mut game = Game::new();
+        game.handle_input("exit").unwrap();
+        assert_eq!(true, game.is_over());
+        assert_eq!(None, game.get_result());
+    }
+    #[test]
+    fn halfmove_clock_resets_on_pawn_moves_and_captures() {
+        let mut game = Game::new();
+        game.handle_input("move g1 f3").unwrap();
+        game.handle_input("move b8 c6").unwrap();
+        assert_eq!(2, game.get_halfmove_clock());
+        game.handle_input("move e2 e4").unwrap();
+        assert_eq!(0, game.get_halfmove_clock());
+        game.handle_input("move c6 d4").unwrap();
+        assert_eq!(1, game.get_halfmove_clock());
+        game.handle_input("move f3 d4").unwrap();
+        assert_eq!(0, game.get_halfmove_clock());
+    }
+
+    #[test]
+    fn fifty_move_rule_can_be_claimed() {
+        let mut game = Game::new();
+        game.halfmove_clock = 98;
+        game.handle_input("move g1 f3").unwrap();
+        assert_eq!(
+            Err(String::from("You can't claim a draw yet.")),
+            game.claim_draw()
+        );
+        game.handle_input("move g8 f6").unwrap();
+        game.handle_input("claim").unwrap();
+        assert_eq!(
+            Some(&GameResult::Draw(EndReason::FiftyMoveRule)),
+            game.get_result()
+        );
+    }
+
+    #[test]
+    fn seventy_five_move_rule_ends_the_game() {
+        let mut game = Game::new();
+        game.halfmove_clock = 148;
+        game.handle_input("move g1 f3").unwrap();
+        assert_eq!(None, game.get_result());
+        game.handle_input("move g8 f6").unwrap();
+        assert_eq!(
+            Some(&GameResult::Draw(EndReason::SeventyFiveMoveRule)),
+            game.get_result()
+        );
+    }
+    fn shuffle_knights(game: &mut Game) {
+        for input in ["move g1 f3", "move g8 f6", "move f3 g1", "move f6 g8"] {
+            game.handle_input(input).unwrap();
+        }
+    }
+
+    #[test]
+    fn threefold_repetition_can_be_claimed() {
+        let mut game = Game::new();
+        shuffle_knights(&mut game);
+        assert_eq!(2, game.get_repetition_count());
+        assert_eq!(true, game.claim_draw().is_err());
+
+        shuffle_knights(&mut game);
+        assert_eq!(3, game.get_repetition_count());
+        game.handle_input("claim").unwrap();
+        assert_eq!(
+            Some(&GameResult::Draw(EndReason::ThreefoldRepetition)),
+            game.get_result()
+        );
+    }
+
+    #[test]
+    fn fivefold_repetition_ends_the_game() {
+        let mut game = Game::new();
+        for _ in 0..3 {
+            shuffle_knights(&mut game);
+        }
+        assert_eq!(None, game.get_result());
+        shuffle_knights(&mut game);
+        assert_eq!(
+            Some(&GameResult::Draw(EndReason::FivefoldRepetition)),
+            game.get_result()
+        );
+    }
+
+    #[test]
+    fn losing_castling_rights_is_a_different_position() {
+        let mut game = Game::new();
+        for input in ["move e2 e4", "move e7 e5", "move e1 e2", "move e8 e7"] {
+            game.handle_input(input).unwrap();
+        }
+        assert_eq!("-", game.get_castling_rights());
+        for input in ["move e2 e1", "move e7 e8", "move e1 e2", "move e8 e7"] {
+            game.handle_input(input).unwrap();
+        }
+        // the kings are back where they were after e4 e5, but they can't castle anymore.
+        assert_eq!(2, game.get_repetition_count());
+    }
+
+    #[test]
+    fn en_passant_only_counts_if_it_can_be_played() {
+        let mut game = Game::new();
+        game.handle_input("move e2 e4").unwrap();
+        assert_eq!(
+            Some(get_row_column(&game, String::from("e3"))),
+            game.get_en_passant_square()
+        );
+        // no black pawn can take it so it's not part of the position.
+        assert_eq!(
+            Game::from_fen("rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq - 0 1")
+                .unwrap()
+                .get_hash(),
+            game.get_hash()
+        );
+        for input in ["move d7 d5", "move e4 e5", "move f7 f5"] {
+            game.handle_input(input).unwrap();
+        }
+        assert_eq!(
+            Game::from_fen("rnbqkbnr/ppp1p1pp/8/3pPp2/8/8/PPPP1PPP/RNBQKBNR w KQkq f6 0 3")
+                .unwrap()
+                .get_hash(),
+            game.get_hash()
+        );
+        assert_ne!(
+            Game::from_fen("rnbqkbnr/ppp1p1pp/8/3pPp2/8/8/PPPP1PPP/RNBQKBNR w KQkq - 0 3")
+                .unwrap()
+                .get_hash(),
+            game.get_hash()
+        );
+    }
+
+    #[test]
+    fn en_passant_does_not_count_if_the_pawn_is_pinned() {
+        // the e5 pawn can't take on d6, its king would be in check from the rook.
+        let game = Game::from_fen("4k3/8/8/r2pP2K/8/8/8/8 w - d6 0 1").unwrap();
+        assert_eq!(
+            Game::from_fen("4k3/8/8/r2pP2K/8/8/8/8 w - - 0 1")
+                .unwrap()
+                .get_hash(),
+            game.get_hash()
+        );
+    }
+
+    #[test]
+    fn incremental_hash_matches_the_whole_position() {
+        let mut game = Game::new();
+        let inputs = [
+            "e4", "d5", "exd5", "c5", "dxc6", "Nf6", "cxb7", "e5", "bxa8=Q", "Bc5", "Nf3", "O-O",
+            "Bc4", "Qe7", "O-O", "Re8",
+        ];
+        for input in inputs {
+            game.handle_input(input).unwrap();
+            assert_eq!(game.compute_hash(), game.get_hash(), "{}", input);
+        }
+        // the same position reached in a different order has the same key.
+        let mut first = Game::new();
+        let mut second = Game::new();
+        for input in ["Nf3", "Nf6", "g3", "g6"] {
+            first.handle_input(input).unwrap();
+        }
+        for input in ["g3", "g6", "Nf3", "Nf6"] {
+            second.handle_input(input).unwrap();
+        }
+        assert_eq!(first.get_hash(), second.get_hash());
+        assert_ne!(Game::new().get_hash(), first.get_hash());
+    }
+
+    #[test]
+    fn taking_the_last_piece_is_a_draw() {
+        let board = Board::create_empty_board();
+        let mut game = Game::inject_board(board);
+        let pieces = [
+            ("e1", PieceType::King, Color::White),
+            ("e8", PieceType::King, Color::Black),
+            ("d2", PieceType::Rook, Color::Black),
+        ];
+        for (square, piece_type, color) in pieces {
+            let piece = piece_type.create_actual_piece(PieceInfo::new(color, true));
+            game.set_piece_row_col(&get_row_column(&game, String::from(square)), piece);
+        }
+        game.handle_input("move e1 d2").unwrap();
+        assert_eq!(
+            Some(&GameResult::Draw(EndReason::InsufficientMaterial)),
+            game.get_result()
+        );
+    }
+    #[test]
+    fn from_fen_plays_like_a_new_game() {
+        let mut game = Game::from_fen(STARTING_POSITION).unwrap();
+        assert_eq!(20, game.legal_moves().len());
+        assert_eq!("KQkq", game.get_castling_rights());
+        assert_eq!(1, game.get_fullmove_number());
+        for input in ["move e2 e4", "move e7 e5"] {
+            game.handle_input(input).unwrap();
+        }
+        assert_eq!(2, game.get_fullmove_number());
+    }
+
+    #[test]
+    fn from_fen_en_passant() {
+        let mut game =
+            Game::from_fen("rnbqkbnr/ppp1p1pp/8/3pPp2/8/8/PPPP1PPP/RNBQKBNR w KQkq f6 0 3")
+                .unwrap();
+        assert_eq!(
+            Some(get_row_column(&game, String::from("f6"))),
+            game.get_en_passant_square()
+        );
+        // d5 moved two squares earlier, only f5 can be taken.
+        assert_eq!(true, game.handle_input("move e5 d6").is_err());
+        game.handle_input("move e5 f6").unwrap();
+        assert_eq!(
+            "rnbqkbnr/ppp1p1pp/5P2/3p4/8/8/PPPP1PPP/RNBQKBNR",
+            game.board.get_placement()
+        );
+    }
+
+    #[test]
+    fn from_fen_castling_rights() {
+        let mut game = Game::from_fen("r3k2r/8/8/8/8/8/8/R3K2R w Kq - 0 1").unwrap();
+        assert_eq!(true, game.handle_input("move e1 c1").is_err());
+        game.handle_input("move e1 g1").unwrap();
+        assert_eq!(true, game.handle_input("move e8 g8").is_err());
+        game.handle_input("move e8 c8").unwrap();
+    }
+
+    #[test]
+    fn from_fen_already_over() {
+        // fool's mate
+        let game = Game::from_fen("rnb1kbnr/pppp1ppp/8/4p3/6Pq/5P2/PPPPP2P/RNBQKBNR w KQkq - 1 3")
+            .unwrap();
+        assert_eq!(
+            Some(&GameResult::BlackWins(EndReason::Checkmate)),
+            game.get_result()
+        );
+    }
+    #[test]
+    fn to_fen() {
+        let mut game = Game::new();
+        assert_eq!(STARTING_POSITION, game.to_fen());
+        game.handle_input("move e2 e4").unwrap();
+        assert_eq!(
+            "rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq e3 0 1",
+            game.to_fen()
+        );
+        for input in ["move g8 f6", "move e1 e2", "move h8 g8"] {
+            game.handle_input(input).unwrap();
+        }
+        assert_eq!(
+            "rnbqkbr1/pppppppp/5n2/8/4P3/8/PPPPKPPP/RNBQ1BNR w q - 3 3",
+            game.to_fen()
+        );
+    }
+    fn assert_undo_restores(fen: &str, inputs: &[&str]) {
+        let mut game = Game::from_fen(fen).unwrap();
+        let mut fens = vec![game.to_fen()];
+        for input in inputs {
+            game.handle_input(input).unwrap();
+            fens.push(game.to_fen());
+        }
+        let placement = game.board.get_placement();
+        for _ in inputs {
+            fens.pop();
+            game.undo().unwrap();
+            assert_eq!(fens.last().unwrap(), &game.to_fen());
+        }
+        assert_eq!(true, game.undo().is_err());
+        for _ in inputs {
+            game.redo().unwrap();
+        }
+        assert_eq!(placement, game.board.get_placement());
+        assert_eq!(true, game.redo().is_err());
+    }
+
+    #[test]
+    fn undo_castling_and_captures() {
+        assert_undo_restores(
+            "r3k2r/pppq1ppp/2n1bn2/4p3/4P3/2N1BN2/PPPQ1PPP/R3K2R w KQkq - 4 9",
+            &["O-O-O", "O-O", "Nxe5", "Nxe5", "Qd7"],
+        );
+        // the pieces are counted again after undoing.
+        let mut game = Game::new();
+        for input in ["e4", "d5", "exd5", "Qxd5", "undo", "undo"] {
+            game.handle_input(input).unwrap();
+        }
+        assert_eq!(
+            8,
+            game.board.get_piece_count(&Color::Black, &PieceType::Pawn)
+        );
+        assert_eq!(
+            8,
+            game.board.get_piece_count(&Color::White, &PieceType::Pawn)
+        );
+    }
+
+    #[test]
+    fn undo_en_passant_and_promotion() {
+        assert_undo_restores(
+            "rnbqkbnr/ppp1p1pp/8/3pPp2/8/8/PPPP1PPP/RNBQKBNR w KQkq f6 0 3",
+            &["exf6", "e6", "f7+", "Ke7", "fxg8=N+"],
+        );
+        // the en passant from the FEN is still there once everything is undone.
+        let mut game =
+            Game::from_fen("rnbqkbnr/ppp1p1pp/8/3pPp2/8/8/PPPP1PPP/RNBQKBNR w KQkq f6 0 3")
+                .unwrap();
+        game.handle_input("Nf3").unwrap();
+        game.handle_input("undo").unwrap();
+        game.handle_input("exf6").unwrap();
+    }
+
+    #[test]
+    fn undo_after_the_game_ended() {
+        let mut game = Game::new();
+        for input in ["f3", "e5", "g4", "Qh4"] {
+            game.handle_input(input).unwrap();
+        }
+        assert_eq!(true, game.is_over());
+        game.handle_input("undo").unwrap();
+        assert_eq!(None, game.get_result());
+        assert_eq!(Color::Black, *game.get_current_color());
+        game.handle_input("d5").unwrap();
+        // the undone move is gone once something else is played.
+        assert_eq!(true, game.redo().is_err());
+    }
+
+    #[test]
+    fn undo_repetitions_and_clocks() {
+        let mut game = Game::new();
+        shuffle_knights(&mut game);
+        shuffle_knights(&mut game);
+        assert_eq!(3, game.get_repetition_count());
+        game.handle_input("undo").unwrap();
+        game.handle_input("undo").unwrap();
+        assert_eq!(2, game.get_repetition_count());
+        assert_eq!(6, game.get_halfmove_clock());
+        assert_eq!(4, game.get_fullmove_number());
+        // black moving takes white's draw offer away, undoing it brings the offer back.
+        for input in ["draw", "Ng1", "Nc6", "undo", "draw"] {
+            game.handle_input(input).unwrap();
+        }
+        assert_eq!(
+            Some(&GameResult::Draw(EndReason::Agreement)),
+            game.get_result()
+        );
+    }
 }
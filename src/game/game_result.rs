@@ -0,0 +1,96 @@
+use std::fmt;
+
+use crate::pieces::color::Color;
+
+/// Why the game ended.
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub enum EndReason {
+    Checkmate,
+    Stalemate,
+    Resignation,
+    Agreement,
+    // claimed once 50 moves were made without moving a pawn or capturing anything.
+    FiftyMoveRule,
+    // same as above but after 75 moves, no one needs to claim it.
+    SeventyFiveMoveRule,
+    // claimed once the same position happened three times.
+    ThreefoldRepetition,
+    // same as above but five times, no one needs to claim it.
+    FivefoldRepetition,
+    // neither player can checkmate with the pieces they have left.
+    InsufficientMaterial,
+    // the reasons a PGN `Termination` tag can give.
+    TimeForfeit,
+    Adjudication,
+    Abandoned,
+    // the result is known but not why, e.g. a PGN without a `Termination` tag.
+    Unknown,
+}
+
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub enum GameResult {
+    WhiteWins(EndReason),
+    BlackWins(EndReason),
+    Draw(EndReason),
+}
+
+impl GameResult {
+    pub fn win_for(color: &Color, reason: EndReason) -> GameResult {
+        match color {
+            Color::White => GameResult::WhiteWins(reason),
+            Color::Black => GameResult::BlackWins(reason),
+        }
+    }
+
+    /// None when it's a draw.
+    pub fn get_winner(&self) -> Option<Color> {
+        match self {
+            GameResult::WhiteWins(_) => Some(Color::White),
+            GameResult::BlackWins(_) => Some(Color::Black),
+            GameResult::Draw(_) => None,
+        }
+    }
+
+    pub fn get_reason(&self) -> &EndReason {
+        match self {
+            GameResult::WhiteWins(reason) => reason,
+            GameResult::BlackWins(reason) => reason,
+            GameResult::Draw(reason) => reason,
+        }
+    }
+}
+
+impl fmt::Display for GameResult {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match (self.get_winner(), self.get_reason()) {
+            (Some(winner), EndReason::Checkmate) => {
+                write!(f, "Checkmate! {} wins!", winner.get_color())
+            }
+            (Some(winner), EndReason::Resignation) => write!(
+                f,
+                "{} resigned. {} wins!",
+                winner.opposite().get_color(),
+                winner.get_color()
+            ),
+            (Some(winner), EndReason::TimeForfeit) => write!(
+                f,
+                "{} ran out of time. {} wins!",
+                winner.opposite().get_color(),
+                winner.get_color()
+            ),
+            (Some(winner), _) => write!(f, "{} wins!", winner.get_color()),
+            (None, EndReason::Stalemate) => write!(f, "Stalemate! It's a draw."),
+            (None, EndReason::Agreement) => write!(f, "Draw by agreement."),
+            (None, EndReason::FiftyMoveRule) => write!(f, "Draw by the fifty-move rule."),
+            (None, EndReason::SeventyFiveMoveRule) => {
+                write!(f, "Draw by the seventy-five-move rule.")
+            }
+            (None, EndReason::ThreefoldRepetition) => write!(f, "Draw by threefold repetition."),
+            (None, EndReason::FivefoldRepetition) => write!(f, "Draw by fivefold repetition."),
+            (None, EndReason::InsufficientMaterial) => {
+                write!(f, "Draw, neither player can checkmate anymore.")
+            }
+            (None, _) => write!(f, "It's a draw."),
+        }
+    }
+}
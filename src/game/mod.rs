@@ -1,4 +1,12 @@
+pub mod bitboard;
 pub mod board;
 mod check_mate_status;
+pub mod fen;
 pub mod game;
+pub mod game_result;
+pub mod movement;
+pub mod perft;
+pub mod pgn;
+mod san;
 pub mod square;
+pub mod zobrist;
@@ -0,0 +1,58 @@
+use std::fmt;
+
+use crate::pieces::piece::PieceType;
+use crate::pieces::validator::row_column::RowColumn;
+
+/// A single move a player can make.
+/// Castling is the king moving two squares, en passant is the pawn moving diagonally to the empty
+/// square. A promotion has the piece the pawn turns into.
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub struct Movement {
+    pub from: RowColumn,
+    pub to: RowColumn,
+    pub promotion: Option<PieceType>,
+}
+
+impl Movement {
+    pub fn new(from: RowColumn, to: RowColumn) -> Movement {
+        Movement {
+            from,
+            to,
+            promotion: None,
+        }
+    }
+
+    pub fn new_promotion(from: RowColumn, to: RowColumn, promotion: PieceType) -> Movement {
+        Movement {
+            from,
+            to,
+            promotion: Some(promotion),
+        }
+    }
+}
+
+/// Coordinate notation, e.g. `e2e4` or `e7e8q`.
+impl fmt::Display for Movement {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}{}", self.from, self.to)?;
+        if let Some(piece_type) = &self.promotion {
+            write!(f, "{}", piece_type.get_name().to_ascii_lowercase())?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn coordinate_notation() {
+        let movement = Movement::new(RowColumn::new(6, 4), RowColumn::new(4, 4));
+        assert_eq!("e2e4", movement.to_string());
+
+        let promotion =
+            Movement::new_promotion(RowColumn::new(1, 0), RowColumn::new(0, 0), PieceType::Queen);
+        assert_eq!("a7a8q", promotion.to_string());
+    }
+}
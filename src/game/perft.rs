@@ -0,0 +1,138 @@
+use crate::game::game::Game;
+use crate::game::movement::Movement;
+
+/// Counting every position a number of moves ahead, to compare against known counts and make
+/// sure the moves are generated right.
+impl Game {
+    /// How many positions can be reached in exactly `depth` plies.
+    pub fn perft(&self, depth: u32) -> u64 {
+        if depth == 0 {
+            return 1;
+        }
+        self.clone().count_nodes(depth)
+    }
+
+    /// Same as perft but split by the first movement, handy to find which one is wrong.
+    pub fn divide(&self, depth: u32) -> Vec<(Movement, u64)> {
+        let mut game = self.clone();
+        let mut divided = vec![];
+        for movement in self.legal_moves() {
+            game.make_legal_movement(&movement);
+            let nodes = match depth {
+                0 | 1 => 1,
+                _ => game.count_nodes(depth - 1),
+            };
+            divided.push((movement, nodes));
+            game.undo().expect("the movement was just played");
+        }
+        divided
+    }
+
+    fn count_nodes(&mut self, depth: u32) -> u64 {
+        let movements = self.legal_moves();
+        if depth == 1 {
+            return movements.len() as u64;
+        }
+        let mut nodes = 0;
+        for movement in &movements {
+            self.make_legal_movement(movement);
+            nodes += self.count_nodes(depth - 1);
+            self.undo().expect("the movement was just played");
+        }
+        nodes
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::game::fen::STARTING_POSITION;
+    use crate::game::game::Game;
+
+    // positions and counts from https://www.chessprogramming.org/Perft_Results
+    fn assert_perft(fen: &str, counts: &[u64]) {
+        let game = Game::from_fen(fen).unwrap();
+        for (depth, count) in counts.iter().enumerate() {
+            assert_eq!(
+                *count,
+                game.perft(depth as u32 + 1),
+                "{} depth {}",
+                fen,
+                depth + 1
+            );
+        }
+    }
+
+    #[test]
+    fn initial_position() {
+        assert_perft(STARTING_POSITION, &[20, 400, 8902, 197281]);
+    }
+
+    #[test]
+    fn kiwipete() {
+        assert_perft(
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+            &[48, 2039, 97862],
+        );
+    }
+
+    #[test]
+    fn position_3() {
+        assert_perft(
+            "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1",
+            &[14, 191, 2812, 43238],
+        );
+    }
+
+    #[test]
+    fn position_4() {
+        assert_perft(
+            "r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1",
+            &[6, 264, 9467, 422333],
+        );
+        // the same position with the colors flipped.
+        assert_perft(
+            "r2q1rk1/pP1p2pp/Q4n2/bbp1p3/Np6/1B3NBn/pPPP1PPP/R3K2R b KQ - 0 1",
+            &[6, 264, 9467, 422333],
+        );
+    }
+
+    #[test]
+    fn position_5() {
+        assert_perft(
+            "rnbq1k1r/pp1Pbppp/2p5/8/2B5/8/PPP1NnPP/RNBQK2R w KQ - 1 8",
+            &[44, 1486, 62379],
+        );
+    }
+
+    #[test]
+    fn position_6() {
+        assert_perft(
+            "r4rk1/1pp1qppp/p1np1n2/2b1p1B1/2B1P1b1/P1NP1N2/1PP1QPPP/R4RK1 w - - 0 10",
+            &[46, 2079, 89890],
+        );
+    }
+
+    #[test]
+    fn divide() {
+        let game = Game::new();
+        let divided = game.divide(3);
+        assert_eq!(20, divided.len());
+        assert_eq!(8902, divided.iter().map(|(_, nodes)| nodes).sum::<u64>());
+        let e2e4 = divided
+            .iter()
+            .find(|(movement, _)| movement.to_string() == "e2e4")
+            .unwrap();
+        assert_eq!(600, e2e4.1);
+        assert_eq!(1, game.perft(0));
+    }
+
+    #[test]
+    fn perft_command() {
+        let mut game = Game::new();
+        game.handle_input("perft 2").unwrap();
+        assert_eq!(true, game.handle_input("perft").is_err());
+        assert_eq!(true, game.handle_input("perft two").is_err());
+        // nothing was played.
+        assert_eq!(STARTING_POSITION, game.to_fen());
+    }
+}
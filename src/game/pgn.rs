@@ -0,0 +1,641 @@
+use std::fmt;
+use std::io;
+use std::iter::Peekable;
+use std::str::Chars;
+
+use crate::game::fen::{FenError, STARTING_POSITION};
+use crate::game::game::Game;
+use crate::game::game_result::{EndReason, GameResult};
+use crate::pieces::color::Color;
+
+// export format keeps the movetext lines under 80 characters.
+const MAX_LINE_LENGTH: usize = 79;
+
+/// The Seven Tag Roster, minus the result which comes from the game itself.
+/// Anything unknown stays `?` like the PGN standard asks for.
+pub struct PgnTags {
+    pub event: String,
+    pub site: String,
+    // YYYY.MM.DD, with `??` for the parts that are unknown.
+    pub date: String,
+    pub round: String,
+    pub white: String,
+    pub black: String,
+}
+
+impl Default for PgnTags {
+    fn default() -> Self {
+        PgnTags {
+            event: String::from("?"),
+            site: String::from("?"),
+            date: String::from("????.??.??"),
+            round: String::from("?"),
+            white: String::from("?"),
+            black: String::from("?"),
+        }
+    }
+}
+
+/// How PGN writes the result, `*` while the game is still going.
+pub fn get_result_token(result: Option<&GameResult>) -> &'static str {
+    match result {
+        Some(GameResult::WhiteWins(_)) => "1-0",
+        Some(GameResult::BlackWins(_)) => "0-1",
+        Some(GameResult::Draw(_)) => "1/2-1/2",
+        None => "*",
+    }
+}
+
+/// The `Termination` tag for the reasons the moves can't show.
+fn get_termination(reason: &EndReason) -> Option<&'static str> {
+    match reason {
+        EndReason::TimeForfeit => Some("time forfeit"),
+        EndReason::Adjudication => Some("adjudication"),
+        EndReason::Abandoned => Some("abandoned"),
+        _ => None,
+    }
+}
+
+/// Anything but the reasons `get_termination` writes, like `normal`, doesn't say why.
+fn parse_termination(termination: &str) -> EndReason {
+    match termination.to_ascii_lowercase().as_str() {
+        "time forfeit" => EndReason::TimeForfeit,
+        "adjudication" => EndReason::Adjudication,
+        "abandoned" => EndReason::Abandoned,
+        _ => EndReason::Unknown,
+    }
+}
+
+fn escape(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+impl Game {
+    /// Writes the game so far in Portable Game Notation.
+    pub fn write_pgn<W: io::Write>(&self, writer: &mut W, tags: &PgnTags) -> io::Result<()> {
+        let result = get_result_token(self.get_result());
+        for (name, value) in [
+            ("Event", &tags.event),
+            ("Site", &tags.site),
+            ("Date", &tags.date),
+            ("Round", &tags.round),
+            ("White", &tags.white),
+            ("Black", &tags.black),
+        ] {
+            writeln!(writer, "[{} \"{}\"]", name, escape(value))?;
+        }
+        writeln!(writer, "[Result \"{}\"]", result)?;
+        if let Some(termination) = self
+            .get_result()
+            .and_then(|result| get_termination(result.get_reason()))
+        {
+            writeln!(writer, "[Termination \"{}\"]", termination)?;
+        }
+        if self.get_starting_position() != STARTING_POSITION {
+            writeln!(writer, "[SetUp \"1\"]")?;
+            writeln!(writer, "[FEN \"{}\"]", self.get_starting_position())?;
+        }
+        writeln!(writer)?;
+
+        let mut tokens = self.get_numbered_movements(None);
+        tokens.push(String::from(result));
+
+        let mut line = String::new();
+        for token in tokens {
+            if !line.is_empty() && line.len() + 1 + token.len() > MAX_LINE_LENGTH {
+                writeln!(writer, "{}", line)?;
+                line.clear();
+            }
+            if !line.is_empty() {
+                line.push(' ');
+            }
+            line.push_str(&token);
+        }
+        writeln!(writer, "{}", line)
+    }
+}
+
+#[derive(Debug, Eq, PartialEq)]
+pub enum PgnError {
+    // a tag pair has to look like [Name "value"].
+    InvalidTag(String),
+    UnclosedComment,
+    // a `(` without its `)` or the other way around.
+    UnbalancedVariation,
+    InvalidFen(FenError),
+    // the ply counting from 1, the move with its number and why it can't be played.
+    IllegalMove(usize, String, String),
+    NoGame,
+}
+
+impl fmt::Display for PgnError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PgnError::InvalidTag(tag) => write!(f, "Invalid tag [{}].", tag),
+            PgnError::UnclosedComment => write!(f, "A comment is missing its closing }}."),
+            PgnError::UnbalancedVariation => {
+                write!(f, "The parentheses of the variations don't match.")
+            }
+            PgnError::InvalidFen(error) => write!(f, "Invalid FEN tag. {}", error),
+            PgnError::IllegalMove(ply, movement, reason) => {
+                write!(f, "Ply {} ({}) can't be played: {}", ply, movement, reason)
+            }
+            PgnError::NoGame => write!(f, "There's no game in there."),
+        }
+    }
+}
+
+/// A game read from PGN, before any of its moves are checked.
+pub struct PgnGame {
+    // in the order they were written.
+    pub tags: Vec<(String, String)>,
+    // the mainline in SAN, without the comments, NAGs and variations.
+    pub moves: Vec<String>,
+    // 1-0, 0-1, 1/2-1/2 or *, if the movetext ended with one.
+    pub result: Option<String>,
+}
+
+impl PgnGame {
+    fn new() -> PgnGame {
+        PgnGame {
+            tags: vec![],
+            moves: vec![],
+            result: None,
+        }
+    }
+
+    fn is_empty(&self) -> bool {
+        self.tags.is_empty() && self.moves.is_empty() && self.result.is_none()
+    }
+
+    pub fn get_tag(&self, name: &str) -> Option<&str> {
+        self.tags
+            .iter()
+            .find(|(tag, _)| tag == name)
+            .map(|(_, value)| value.as_str())
+    }
+
+    /// Plays the mainline from the start, or from the FEN tag if there's one.
+    /// Stops at the first move that isn't legal.
+    /// A result the moves don't explain, like `1-0` without a checkmate, ends the game for the
+    /// reason in the `Termination` tag, or an unknown one. `1/2-1/2` is a claimed draw if one
+    /// could be claimed.
+    pub fn replay(&self) -> Result<Game, PgnError> {
+        let mut game = match self.get_tag("FEN") {
+            Some(fen) => Game::from_fen(fen).map_err(PgnError::InvalidFen)?,
+            None => Game::new(),
+        };
+        for (index, san) in self.moves.iter().enumerate() {
+            let numbered = match game.get_current_color() {
+                Color::White => format!("{}. {}", game.get_fullmove_number(), san),
+                Color::Black => format!("{}... {}", game.get_fullmove_number(), san),
+            };
+            let illegal_move = |reason| PgnError::IllegalMove(index + 1, numbered.clone(), reason);
+            let movement = game.parse_san(san).map_err(illegal_move)?;
+            game.play_movement(&movement).map_err(illegal_move)?;
+        }
+        if !game.is_over() {
+            let reason = match self.get_tag("Termination") {
+                Some(termination) => parse_termination(termination),
+                None => EndReason::Unknown,
+            };
+            match self.result.as_deref() {
+                Some("1-0") => game.end(GameResult::WhiteWins(reason)),
+                Some("0-1") => game.end(GameResult::BlackWins(reason)),
+                // claiming ends the game when a draw could be claimed.
+                Some("1/2-1/2") if game.claim_draw().is_err() => game.end(GameResult::Draw(reason)),
+                _ => {}
+            }
+        }
+        Ok(game)
+    }
+}
+
+/// Splits a PGN file into its games. Comments, NAGs, variations and escaped lines are skipped.
+pub fn parse_pgn(pgn: &str) -> Result<Vec<PgnGame>, PgnError> {
+    let mut games = vec![];
+    let mut game = PgnGame::new();
+    // how many variations deep we are, 0 is the mainline.
+    let mut depth = 0;
+    let mut previous = '\n';
+    let mut chars = pgn.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            // a line starting with % is ignored.
+            '%' if previous == '\n' => skip_line(&mut chars),
+            ';' => skip_line(&mut chars),
+            '{' => loop {
+                match chars.next() {
+                    Some('}') => break,
+                    Some(_) => {}
+                    None => return Err(PgnError::UnclosedComment),
+                }
+            },
+            '(' => depth += 1,
+            ')' => {
+                if depth == 0 {
+                    return Err(PgnError::UnbalancedVariation);
+                }
+                depth -= 1;
+            }
+            '[' if depth == 0 => {
+                // tags right after movetext without a result start the next game.
+                if !game.moves.is_empty() {
+                    games.push(std::mem::replace(&mut game, PgnGame::new()));
+                }
+                game.tags.push(parse_tag(&mut chars)?);
+            }
+            c if c.is_whitespace() => {}
+            c => {
+                let mut symbol = String::from(c);
+                while let Some(next) = chars.peek() {
+                    if next.is_whitespace() || "{}()[];".contains(*next) {
+                        break;
+                    }
+                    symbol.push(*next);
+                    chars.next();
+                }
+                if depth > 0 || symbol.starts_with('$') {
+                    continue;
+                }
+                match symbol.as_str() {
+                    "1-0" | "0-1" | "1/2-1/2" | "*" => {
+                        game.result = Some(symbol);
+                        games.push(std::mem::replace(&mut game, PgnGame::new()));
+                    }
+                    _ => {
+                        let san = strip_move_number(&symbol);
+                        if !san.is_empty() {
+                            game.moves.push(san.to_string());
+                        }
+                    }
+                }
+            }
+        }
+        previous = c;
+    }
+    if depth > 0 {
+        return Err(PgnError::UnbalancedVariation);
+    }
+    if !game.is_empty() {
+        games.push(game);
+    }
+    Ok(games)
+}
+
+/// Move numbers can be glued to the move, e.g. 1.e4 or 12...Nf6. Only digits followed by dots
+/// are a move number, 0-0 is castling.
+fn strip_move_number(symbol: &str) -> &str {
+    let without_digits = symbol.trim_start_matches(|c: char| c.is_ascii_digit());
+    match without_digits.starts_with('.') {
+        true => without_digits.trim_start_matches('.'),
+        false => symbol,
+    }
+}
+
+fn skip_line(chars: &mut Peekable<Chars>) {
+    for c in chars.by_ref() {
+        if c == '\n' {
+            break;
+        }
+    }
+}
+
+/// Reads `Name "value"]`, the `[` was already read.
+fn parse_tag(chars: &mut Peekable<Chars>) -> Result<(String, String), PgnError> {
+    let mut tag = String::new();
+    let mut in_quotes = false;
+    let mut is_closed = false;
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' if in_quotes => {
+                if let Some(escaped) = chars.next() {
+                    tag.push(escaped);
+                }
+                continue;
+            }
+            '"' => in_quotes = !in_quotes,
+            ']' if !in_quotes => {
+                is_closed = true;
+                break;
+            }
+            _ => {}
+        }
+        tag.push(c);
+    }
+    let invalid_tag = || PgnError::InvalidTag(tag.clone());
+    if !is_closed {
+        return Err(invalid_tag());
+    }
+    let (name, value) = tag
+        .trim()
+        .split_once(char::is_whitespace)
+        .ok_or_else(invalid_tag)?;
+    let value = value.trim();
+    if name.is_empty()
+        || !name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+        || value.len() < 2
+        || !value.starts_with('"')
+        || !value.ends_with('"')
+    {
+        return Err(invalid_tag());
+    }
+    Ok((name.to_string(), value[1..value.len() - 1].to_string()))
+}
+
+impl Game {
+    /// Replays the first game of a PGN.
+    pub fn from_pgn(pgn: &str) -> Result<Game, PgnError> {
+        match parse_pgn(pgn)?.first() {
+            Some(game) => game.replay(),
+            None => Err(PgnError::NoGame),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::game::fen::FenError;
+    use crate::game::game::Game;
+    use crate::game::game_result::{EndReason, GameResult};
+    use crate::game::pgn::{parse_pgn, PgnError, PgnTags};
+    use crate::pieces::color::Color;
+
+    fn to_pgn(game: &Game, tags: &PgnTags) -> String {
+        let mut pgn = vec![];
+        game.write_pgn(&mut pgn, tags).unwrap();
+        String::from_utf8(pgn).unwrap()
+    }
+
+    #[test]
+    fn finished_game() {
+        let mut game = Game::new();
+        for input in ["e4", "e5", "Bc4", "Nc6", "Qh5", "Nf6", "Qxf7"] {
+            game.handle_input(input).unwrap();
+        }
+        let tags = PgnTags {
+            event: String::from("Casual \"blitz\""),
+            site: String::from("Toronto"),
+            date: String::from("2024.05.01"),
+            round: String::from("1"),
+            white: String::from("Alice"),
+            black: String::from("Bob"),
+        };
+        assert_eq!(
+            "[Event \"Casual \\\"blitz\\\"\"]
+[Site \"Toronto\"]
+[Date \"2024.05.01\"]
+[Round \"1\"]
+[White \"Alice\"]
+[Black \"Bob\"]
+[Result \"1-0\"]
+
+1. e4 e5 2. Bc4 Nc6 3. Qh5 Nf6 4. Qxf7# 1-0
+",
+            to_pgn(&game, &tags)
+        );
+    }
+
+    #[test]
+    fn game_in_progress_from_a_position() {
+        let mut game = Game::from_fen("r3k2r/8/8/8/8/8/8/R3K2R b KQkq - 4 20").unwrap();
+        for input in ["O-O-O", "Rxh8", "Rxh8"] {
+            game.handle_input(input).unwrap();
+        }
+        assert_eq!(
+            "[Event \"?\"]
+[Site \"?\"]
+[Date \"????.??.??\"]
+[Round \"?\"]
+[White \"?\"]
+[Black \"?\"]
+[Result \"*\"]
+[SetUp \"1\"]
+[FEN \"r3k2r/8/8/8/8/8/8/R3K2R b KQkq - 4 20\"]
+
+20... O-O-O 21. Rxh8 Rxh8 *
+",
+            to_pgn(&game, &PgnTags::default())
+        );
+    }
+
+    #[test]
+    fn long_games_wrap() {
+        let mut game = Game::new();
+        for _ in 0..3 {
+            for input in ["Nf3", "Nf6", "Ng1", "Ng8"] {
+                game.handle_input(input).unwrap();
+            }
+        }
+        for input in ["e4", "e5", "d4", "d5", "c4", "c6"] {
+            game.handle_input(input).unwrap();
+        }
+        game.handle_input("resign").unwrap();
+        let pgn = to_pgn(&game, &PgnTags::default());
+        let movetext: Vec<&str> = pgn.split("\n\n").nth(1).unwrap().lines().collect();
+        assert_eq!(2, movetext.len());
+        assert!(movetext.iter().all(|line| line.len() < 80));
+        assert!(movetext[1].ends_with("9. c4 c6 0-1"));
+    }
+
+    #[test]
+    fn round_trip() {
+        let mut game = Game::from_fen("4k3/P7/8/8/8/8/8/4K3 w - - 0 40").unwrap();
+        for input in ["a8=Q+", "Kd7", "Qb7+", "Ke6"] {
+            game.handle_input(input).unwrap();
+        }
+        let pgn = to_pgn(&game, &PgnTags::default());
+        let imported = Game::from_pgn(&pgn).unwrap();
+        assert_eq!(game.to_fen(), imported.to_fen());
+        assert_eq!(pgn, to_pgn(&imported, &PgnTags::default()));
+    }
+
+    #[test]
+    fn comments_nags_and_variations() {
+        let pgn = r#"[Event "F/S Return Match"]
+[Site "Belgrade, Serbia JUG"]
+[White "Fischer, Robert J."]
+[Black "Spassky, Boris V."]
+[Annotator "Someone \"quoted\""]
+[Result "1-0"]
+
+% an escaped line 1. h4
+1. e4 {the king's pawn (not the queen's)} e5 $1 2.Nf3 Nc6 (2... d6 3. d4 (3. Bc4 Be7)
+exd4) 3. Bb5!? ; a comment until the end of the line 3. Bc4
+3...a6 4. Ba4 Nf6 5. O-O 1-0
+"#;
+        let games = parse_pgn(pgn).unwrap();
+        assert_eq!(1, games.len());
+        let game = &games[0];
+        assert_eq!(Some("Fischer, Robert J."), game.get_tag("White"));
+        assert_eq!(Some("Someone \"quoted\""), game.get_tag("Annotator"));
+        assert_eq!(None, game.get_tag("Round"));
+        assert_eq!(Some("1-0"), game.result.as_deref());
+        assert_eq!(
+            vec!["e4", "e5", "Nf3", "Nc6", "Bb5!?", "a6", "Ba4", "Nf6", "O-O"],
+            game.moves
+        );
+        assert_eq!(
+            "r1bqkb1r/1ppp1ppp/p1n2n2/4p3/B3P3/5N2/PPPP1PPP/RNBQ1RK1 b kq - 3 5",
+            game.replay().unwrap().to_fen()
+        );
+    }
+
+    #[test]
+    fn castling_with_zeros() {
+        let pgn = "1. e4 e5 2. Nf3 Nc6 3. Bc4 d6 4.0-0 Be6 5. d3 Qd7 6. Nc3 0-0-0 *";
+        let games = parse_pgn(pgn).unwrap();
+        assert_eq!("0-0", games[0].moves[6]);
+        assert_eq!("0-0-0", games[0].moves[11]);
+        assert_eq!(
+            "2kr1bnr/pppq1ppp/2npb3/4p3/2B1P3/2NP1N2/PPP2PPP/R1BQ1RK1 w - - 3 7",
+            games[0].replay().unwrap().to_fen()
+        );
+    }
+
+    #[test]
+    fn multiple_games() {
+        let pgn = r#"[Event "one"]
+[Result "0-1"]
+
+1. f3 e5 2. g4 Qh4# 0-1
+
+[Event "two"]
+
+1. e4 e5
+
+[Event "three"]
+[SetUp "1"]
+[FEN "4k3/8/8/8/8/8/8/4K2R w K - 0 1"]
+
+1. O-O *"#;
+        let games = parse_pgn(pgn).unwrap();
+        assert_eq!(3, games.len());
+        assert_eq!(
+            Some(&GameResult::BlackWins(EndReason::Checkmate)),
+            games[0].replay().unwrap().get_result()
+        );
+        assert_eq!(Some("two"), games[1].get_tag("Event"));
+        assert_eq!(None, games[1].result);
+        assert_eq!(2, games[1].replay().unwrap().get_movements().len());
+        assert_eq!(
+            "4k3/8/8/8/8/8/8/5RK1 b - - 1 1",
+            games[2].replay().unwrap().to_fen()
+        );
+    }
+
+    #[test]
+    fn results_without_a_checkmate() {
+        // nothing says why, white could have resigned or lost on time.
+        let game = Game::from_pgn("1. e4 e5 2. f3 0-1").unwrap();
+        assert_eq!(
+            Some(&GameResult::BlackWins(EndReason::Unknown)),
+            game.get_result()
+        );
+        assert_eq!("B wins!", game.get_result().unwrap().to_string());
+        let game = Game::from_pgn("1. e4 e5 1/2-1/2").unwrap();
+        assert_eq!(
+            Some(&GameResult::Draw(EndReason::Unknown)),
+            game.get_result()
+        );
+        let game = Game::from_pgn("[Termination \"normal\"]\n1. e4 e5 2. Nf3 1-0").unwrap();
+        assert_eq!(
+            Some(&GameResult::WhiteWins(EndReason::Unknown)),
+            game.get_result()
+        );
+
+        // the tag does.
+        let pgn = "[Termination \"time forfeit\"]\n1. e4 e5 2. f3 1-0";
+        let game = Game::from_pgn(pgn).unwrap();
+        assert_eq!(
+            Some(&GameResult::WhiteWins(EndReason::TimeForfeit)),
+            game.get_result()
+        );
+        assert_eq!(
+            "B ran out of time. W wins!",
+            game.get_result().unwrap().to_string()
+        );
+        // and is written back.
+        let written = to_pgn(&game, &PgnTags::default());
+        assert_eq!(true, written.contains("[Termination \"time forfeit\"]"));
+        assert_eq!(
+            game.get_result(),
+            Game::from_pgn(&written).unwrap().get_result()
+        );
+        let pgn = "[Termination \"adjudication\"]\n1. e4 e5 1/2-1/2";
+        assert_eq!(
+            Some(&GameResult::Draw(EndReason::Adjudication)),
+            Game::from_pgn(pgn).unwrap().get_result()
+        );
+
+        // a resigned game only says it was won.
+        let mut game = Game::new();
+        game.handle_input("e4").unwrap();
+        game.resign();
+        let written = to_pgn(&game, &PgnTags::default());
+        assert_eq!(false, written.contains("Termination"));
+        assert_eq!(
+            Some(&GameResult::WhiteWins(EndReason::Unknown)),
+            Game::from_pgn(&written).unwrap().get_result()
+        );
+        // the board says how it really ended.
+        let game = Game::from_pgn("1. f3 e5 2. g4 Qh4# 1/2-1/2").unwrap();
+        assert_eq!(
+            Some(&GameResult::BlackWins(EndReason::Checkmate)),
+            game.get_result()
+        );
+        assert_eq!(None, Game::from_pgn("1. e4 e5 *").unwrap().get_result());
+    }
+
+    #[test]
+    fn illegal_moves_report_the_ply() {
+        let error = Game::from_pgn("1. e4 e5 2. Nf3 Nf6 3. Ke3 *")
+            .err()
+            .unwrap();
+        assert_eq!(
+            PgnError::IllegalMove(
+                5,
+                String::from("3. Ke3"),
+                String::from("Ke3 isn't a legal move.")
+            ),
+            error
+        );
+        assert_eq!(
+            "Ply 5 (3. Ke3) can't be played: Ke3 isn't a legal move.",
+            error.to_string()
+        );
+
+        let error = Game::from_pgn("1. f3 e5 2. g4 Qh4# 3. a3 0-1")
+            .err()
+            .unwrap();
+        assert_eq!(
+            PgnError::IllegalMove(5, String::from("3. a3"), String::from("The game is over.")),
+            error
+        );
+    }
+
+    #[test]
+    fn malformed_pgns() {
+        let cases = [
+            (
+                "[Event \"unclosed]\n1. e4 *",
+                PgnError::InvalidTag(String::from("Event \"unclosed]\n1. e4 *")),
+            ),
+            (
+                "[Event unquoted]",
+                PgnError::InvalidTag(String::from("Event unquoted")),
+            ),
+            ("1. e4 {no end", PgnError::UnclosedComment),
+            ("1. e4 (1. d4 *", PgnError::UnbalancedVariation),
+            ("1. e4 ) *", PgnError::UnbalancedVariation),
+            (
+                "[FEN \"8/8/8/8/8/8/8/8 w - - 0 1\"] *",
+                PgnError::InvalidFen(FenError::InvalidKingCount(Color::White, 0)),
+            ),
+            ("", PgnError::NoGame),
+        ];
+        for (pgn, error) in cases {
+            assert_eq!(Some(error), Game::from_pgn(pgn).err(), "{}", pgn);
+        }
+    }
+}
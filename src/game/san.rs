@@ -0,0 +1,443 @@
+use crate::board_movements::{C, G};
+use crate::game::game::Game;
+use crate::game::movement::Movement;
+use crate::pieces::color::Color;
+use crate::pieces::piece::PieceType;
+use crate::pieces::validator::row_column::RowColumn;
+
+/// Standard Algebraic Notation, e.g. `Nf3`, `exd5`, `O-O-O`, `Rad1` or `e8=Q+`.
+impl Game {
+    /// Writes a legal movement of the player whose turn it is in SAN.
+    pub fn to_san(&self, movement: &Movement) -> String {
+        let board = self.get_board();
+        let piece_type = match board.squares[movement.from.row as usize]
+            [movement.from.column as usize]
+            .get_piece_type()
+        {
+            Some(piece_type) => piece_type,
+            None => return movement.to_string(),
+        };
+        let mut san = String::new();
+        let column_difference = movement.to.column as i8 - movement.from.column as i8;
+        if piece_type == PieceType::King && column_difference.abs() == 2 {
+            san.push_str(if movement.to.column == G {
+                "O-O"
+            } else {
+                "O-O-O"
+            });
+        } else {
+            let is_capture = board.squares[movement.to.row as usize]
+                [movement.to.column as usize]
+                .has_piece()
+                // en passant is the only way a pawn moves diagonally to an empty square.
+                || (piece_type == PieceType::Pawn && column_difference != 0);
+            if piece_type == PieceType::Pawn {
+                if is_capture {
+                    san.push(get_file(&movement.from));
+                }
+            } else {
+                san.push(piece_type.get_name());
+                san.push_str(&self.get_disambiguation(movement, &piece_type));
+            }
+            if is_capture {
+                san.push('x');
+            }
+            san.push_str(&movement.to.to_string());
+            if let Some(promotion) = &movement.promotion {
+                san.push('=');
+                san.push(promotion.get_name());
+            }
+        }
+
+        let mut after = self.clone();
+        if after.play_movement(movement).is_ok()
+            && after
+                .get_board()
+                .is_king_in_check(after.get_current_color())
+        {
+            san.push(if after.legal_moves().is_empty() {
+                '#'
+            } else {
+                '+'
+            });
+        }
+        san
+    }
+
+    /// Finds the legal movement written in coordinate notation, e.g. `e2e4` or `e7e8q`, the way
+    /// engines and GUIs send them.
+    pub fn parse_coordinate_notation(&self, notation: &str) -> Result<Movement, String> {
+        let notation = notation.to_ascii_lowercase();
+        self.legal_moves()
+            .into_iter()
+            .find(|movement| movement.to_string() == notation)
+            .ok_or_else(|| format!("{} isn't a legal move.", notation))
+    }
+
+    /// Finds the legal movement `san` describes. Check marks and annotations like `!?` are
+    /// optional, and so is the `x` of a capture.
+    pub fn parse_san(&self, san: &str) -> Result<Movement, String> {
+        if self.is_over() {
+            return Err(String::from("The game is over."));
+        }
+        let invalid_san = || format!("{} isn't a move, try something like Nf3 or exd5.", san);
+        let trimmed = san.trim_end_matches(['+', '#', '!', '?']);
+        let legal_moves = self.legal_moves();
+
+        if let Some(column) = match trimmed {
+            "O-O" | "0-0" => Some(G),
+            "O-O-O" | "0-0-0" => Some(C),
+            _ => None,
+        } {
+            return legal_moves
+                .into_iter()
+                .find(|movement| {
+                    self.get_piece_type(&movement.from) == Some(PieceType::King)
+                        && movement.from.column.abs_diff(movement.to.column) == 2
+                        && movement.to.column == column
+                })
+                .ok_or_else(|| String::from("You can't castle to that side."));
+        }
+
+        let mut chars: Vec<char> = trimmed.chars().collect();
+        let piece_type = match chars.first() {
+            Some(c @ ('N' | 'B' | 'R' | 'Q' | 'K')) => {
+                let piece_type = PieceType::from_char(*c);
+                chars.remove(0);
+                piece_type.unwrap()
+            }
+            _ => PieceType::Pawn,
+        };
+        // `e8=Q` and `e8Q` are both fine.
+        let promotion = match chars.last() {
+            Some(c @ ('N' | 'B' | 'R' | 'Q')) if piece_type == PieceType::Pawn => {
+                let promotion = PieceType::from_char(*c);
+                chars.pop();
+                if chars.last() == Some(&'=') {
+                    chars.pop();
+                }
+                promotion
+            }
+            _ => None,
+        };
+        chars.retain(|c| *c != 'x');
+        if chars.len() < 2 || chars.len() > 4 {
+            return Err(invalid_san());
+        }
+        let to =
+            parse_square(chars[chars.len() - 2], chars[chars.len() - 1]).ok_or_else(invalid_san)?;
+        let mut from_file = None;
+        let mut from_rank = None;
+        for c in &chars[..chars.len() - 2] {
+            match c {
+                'a'..='h' if from_file.is_none() => from_file = Some(*c),
+                '1'..='8' if from_rank.is_none() => from_rank = Some(*c),
+                _ => return Err(invalid_san()),
+            }
+        }
+
+        let mut candidates: Vec<Movement> = legal_moves
+            .into_iter()
+            .filter(|movement| {
+                movement.to == to
+                    && self.get_piece_type(&movement.from).as_ref() == Some(&piece_type)
+                    && from_file.is_none_or(|file| get_file(&movement.from) == file)
+                    && from_rank.is_none_or(|rank| get_rank(&movement.from) == rank)
+            })
+            .collect();
+        if candidates
+            .iter()
+            .any(|movement| movement.promotion.is_some())
+        {
+            if promotion.is_none() {
+                return Err(String::from("We need to know what piece you want"));
+            }
+            candidates.retain(|movement| movement.promotion == promotion);
+        } else if promotion.is_some() {
+            return Err(String::from(
+                "Only a pawn reaching the last row can be promoted.",
+            ));
+        }
+        match candidates.len() {
+            0 => Err(format!("{} isn't a legal move.", san)),
+            1 => Ok(candidates.remove(0)),
+            _ => Err(format!(
+                "{} could be more than one move, add the file or row the piece is on.",
+                san
+            )),
+        }
+    }
+
+    /// The movements played so far in SAN with their move numbers, e.g. `1. e4 e5 2. Nf3`.
+    /// With `plies`, only that many of the last ones.
+    pub fn get_numbered_movements(&self, plies: Option<usize>) -> Vec<String> {
+        let mut replay = Game::from_fen(self.get_starting_position())
+            .expect("the game started from a valid position");
+        let mut numbered = vec![];
+        for movement in self.get_movements() {
+            numbered.push((
+                replay.get_fullmove_number(),
+                replay.get_current_color().clone(),
+                replay.to_san(&movement),
+            ));
+            replay
+                .play_movement(&movement)
+                .expect("the game only has legal movements");
+        }
+        let skipped = match plies {
+            Some(plies) => numbered.len().saturating_sub(plies),
+            None => 0,
+        };
+        let mut tokens = vec![];
+        for (index, (fullmove_number, color, san)) in numbered.into_iter().skip(skipped).enumerate()
+        {
+            match color {
+                Color::White => tokens.push(format!("{}.", fullmove_number)),
+                Color::Black if index == 0 => tokens.push(format!("{}...", fullmove_number)),
+                Color::Black => {}
+            }
+            tokens.push(san);
+        }
+        tokens
+    }
+
+    /// The file, the rank or both of the piece moving, but only if another piece of the same
+    /// type could also go to the same square.
+    fn get_disambiguation(&self, movement: &Movement, piece_type: &PieceType) -> String {
+        let others: Vec<RowColumn> = self
+            .legal_moves()
+            .into_iter()
+            .filter(|other| {
+                other.to == movement.to
+                    && other.from != movement.from
+                    && self.get_piece_type(&other.from).as_ref() == Some(piece_type)
+            })
+            .map(|other| other.from)
+            .collect();
+        if others.is_empty() {
+            String::new()
+        } else if others
+            .iter()
+            .all(|other| other.column != movement.from.column)
+        {
+            get_file(&movement.from).to_string()
+        } else if others.iter().all(|other| other.row != movement.from.row) {
+            get_rank(&movement.from).to_string()
+        } else {
+            movement.from.to_string()
+        }
+    }
+
+    fn get_piece_type(&self, location: &RowColumn) -> Option<PieceType> {
+        self.get_board().squares[location.row as usize][location.column as usize].get_piece_type()
+    }
+}
+
+fn get_file(location: &RowColumn) -> char {
+    (b'a' + location.column) as char
+}
+
+fn get_rank(location: &RowColumn) -> char {
+    (b'8' - location.row) as char
+}
+
+fn parse_square(file: char, rank: char) -> Option<RowColumn> {
+    match (file, rank) {
+        ('a'..='h', '1'..='8') => Some(RowColumn::new(b'8' - rank as u8, file as u8 - b'a')),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::game::game::Game;
+    use crate::pieces::piece::PieceType;
+
+    fn play(game: &mut Game, moves: &[&str]) {
+        for san in moves {
+            let movement = game.parse_san(san).unwrap();
+            game.play_movement(&movement).unwrap();
+        }
+    }
+
+    fn all_san(game: &Game) -> Vec<String> {
+        let mut moves: Vec<String> = game
+            .legal_moves()
+            .iter()
+            .map(|movement| game.to_san(movement))
+            .collect();
+        moves.sort();
+        moves
+    }
+
+    #[test]
+    fn opening_moves() {
+        let mut game = Game::new();
+        assert_eq!(
+            vec![
+                "Na3", "Nc3", "Nf3", "Nh3", "a3", "a4", "b3", "b4", "c3", "c4", "d3", "d4", "e3",
+                "e4", "f3", "f4", "g3", "g4", "h3", "h4"
+            ],
+            all_san(&game)
+        );
+        play(&mut game, &["e4", "d5", "exd5", "Qxd5", "Nc3", "Qa5"]);
+        assert_eq!(
+            "rnb1kbnr/ppp1pppp/8/q7/8/2N5/PPPP1PPP/R1BQKBNR w KQkq - 2 4",
+            game.to_fen()
+        );
+    }
+
+    #[test]
+    fn castling() {
+        let mut game =
+            Game::from_fen("r3k2r/pppq1ppp/2n1bn2/4p3/4P3/2N1BN2/PPPQ1PPP/R3K2R w KQkq - 0 1")
+                .unwrap();
+        play(&mut game, &["O-O-O", "0-0"]);
+        assert_eq!("-", game.get_castling_rights());
+        assert_eq!(
+            "r4rk1/pppq1ppp/2n1bn2/4p3/4P3/2N1BN2/PPPQ1PPP/2KR3R w - - 2 2",
+            game.to_fen()
+        );
+        assert_eq!(true, game.parse_san("O-O").is_err());
+    }
+
+    #[test]
+    fn disambiguation() {
+        let game = Game::from_fen("3r3k/8/8/R7/4N3/8/8/R3KN2 w - - 0 1").unwrap();
+        let moves = all_san(&game);
+        // both rooks can go to a3, only the a1 rook to d1.
+        assert!(moves.contains(&String::from("R5a3")));
+        assert!(moves.contains(&String::from("R1a3")));
+        assert!(moves.contains(&String::from("Rd1")));
+        // both knights can go to d2 and g3.
+        assert!(moves.contains(&String::from("Ned2")));
+        assert!(moves.contains(&String::from("Nfd2")));
+        assert!(moves.contains(&String::from("Ng3")) == false);
+
+        assert_eq!("a5a3", game.parse_san("R5a3").unwrap().to_string());
+        assert_eq!("e4d2", game.parse_san("Ned2").unwrap().to_string());
+        assert_eq!("e4d2", game.parse_san("Ne4d2").unwrap().to_string());
+        assert_eq!(true, game.parse_san("Ra3").is_err());
+        assert_eq!(true, game.parse_san("Nd2").is_err());
+    }
+
+    #[test]
+    fn disambiguation_needs_the_whole_square() {
+        let game = Game::from_fen("7k/8/8/8/Q1Q5/8/Q7/4K3 w - - 0 1").unwrap();
+        let moves = all_san(&game);
+        assert!(moves.contains(&String::from("Qa4b3")));
+        assert!(moves.contains(&String::from("Qab3")) == false);
+        assert_eq!("a4b3", game.parse_san("Qa4b3").unwrap().to_string());
+    }
+
+    #[test]
+    fn promotion_check_and_checkmate() {
+        let game = Game::from_fen("1n5k/P7/8/8/8/8/8/R3K3 w Q - 0 1").unwrap();
+        let moves = all_san(&game);
+        for san in [
+            "a8=Q", "a8=N", "axb8=Q+", "axb8=R+", "axb8=B", "O-O-O", "Ra6",
+        ] {
+            assert!(moves.contains(&String::from(san)), "{}", san);
+        }
+        assert_eq!("a7b8n", game.parse_san("axb8=N").unwrap().to_string());
+        assert_eq!("a7a8q", game.parse_san("a8Q").unwrap().to_string());
+        assert_eq!(true, game.parse_san("a8").is_err());
+        assert_eq!(true, game.parse_san("Ra6=Q").is_err());
+
+        let mut game = Game::new();
+        play(&mut game, &["f3", "e5", "g4"]);
+        let mate = game.parse_san("Qh4#").unwrap();
+        assert_eq!("Qh4#", game.to_san(&mate));
+    }
+
+    #[test]
+    fn en_passant() {
+        let game = Game::from_fen("rnbqkbnr/ppp1p1pp/8/3pPp2/8/8/PPPP1PPP/RNBQKBNR w KQkq f6 0 3")
+            .unwrap();
+        let movement = game.parse_san("exf6").unwrap();
+        assert_eq!("e5f6", movement.to_string());
+        assert_eq!("exf6", game.to_san(&movement));
+    }
+
+    #[test]
+    fn invalid_san() {
+        let game = Game::new();
+        for san in ["", "Nf", "Zf3", "e9", "Nf3g", "i4", "Nf4", "e5"] {
+            assert_eq!(true, game.parse_san(san).is_err(), "{}", san);
+        }
+    }
+
+    #[test]
+    fn coordinate_notation() {
+        let mut game = Game::new();
+        let movement = game.parse_coordinate_notation("g1f3").unwrap();
+        assert_eq!("Nf3", game.to_san(&movement));
+        game.play_movement(&movement).unwrap();
+        for notation in ["g1f3", "e7e4", "e7", "e7e8q", ""] {
+            assert_eq!(
+                true,
+                game.parse_coordinate_notation(notation).is_err(),
+                "{}",
+                notation
+            );
+        }
+
+        let game = Game::from_fen("8/4P1k1/8/8/8/8/8/4K3 w - - 0 1").unwrap();
+        let movement = game.parse_coordinate_notation("e7e8n").unwrap();
+        assert_eq!(Some(PieceType::Knight), movement.promotion);
+        assert_eq!(true, game.parse_coordinate_notation("e7e8").is_err());
+    }
+
+    #[test]
+    fn handle_input_accepts_san() {
+        let mut game = Game::new();
+        for input in ["e4", "e5", "Nf3", "Nc6", "Bb5", "a6", "Bxc6", "dxc6", "O-O"] {
+            game.handle_input(input).unwrap();
+        }
+        assert_eq!(
+            "r1bqkbnr/1pp2ppp/p1p5/4p3/4P3/5N2/PPPP1PPP/RNBQ1RK1 b kq - 1 5",
+            game.to_fen()
+        );
+        assert_eq!(true, game.handle_input("Nf3").is_err());
+    }
+
+    #[test]
+    fn numbered_movements() {
+        let mut game = Game::new();
+        assert_eq!(Vec::<String>::new(), game.get_numbered_movements(None));
+        play(&mut game, &["e4", "e5", "Nf3", "Nc6", "Bb5"]);
+        assert_eq!(
+            "1. e4 e5 2. Nf3 Nc6 3. Bb5",
+            game.get_numbered_movements(None).join(" ")
+        );
+        assert_eq!(
+            "2... Nc6 3. Bb5",
+            game.get_numbered_movements(Some(2)).join(" ")
+        );
+        assert_eq!(
+            "2. Nf3 Nc6 3. Bb5",
+            game.get_numbered_movements(Some(3)).join(" ")
+        );
+        assert_eq!(
+            "1. e4 e5 2. Nf3 Nc6 3. Bb5",
+            game.get_numbered_movements(Some(100)).join(" ")
+        );
+        assert_eq!(Vec::<String>::new(), game.get_numbered_movements(Some(0)));
+
+        let mut game = Game::from_fen("4k3/8/8/8/8/8/8/4K2R b K - 0 30").unwrap();
+        play(&mut game, &["Kd7", "O-O", "Kc6"]);
+        assert_eq!(
+            "30... Kd7 31. O-O Kc6",
+            game.get_numbered_movements(None).join(" ")
+        );
+    }
+
+    #[test]
+    fn history_command() {
+        let mut game = Game::new();
+        for input in ["history", "e4", "history", "history 1"] {
+            game.handle_input(input).unwrap();
+        }
+        assert_eq!(true, game.handle_input("history ten").is_err());
+    }
+}
@@ -0,0 +1,134 @@
+use crate::game::bitboard::{color_index, piece_index, square_index};
+use crate::game::board::Board;
+use crate::pieces::color::Color;
+use crate::pieces::piece::Pieces;
+use crate::pieces::validator::row_column::RowColumn;
+
+/// Random numbers for a Zobrist key. A position's key is every number that applies to it XORed
+/// together, so a movement only has to XOR in and out what it changed.
+pub struct ZobristKeys {
+    // by color and piece type, then by square.
+    pieces: [[u64; 64]; 12],
+    black_to_move: u64,
+    // KQkq, the way FEN writes the castling rights.
+    castling: [u64; 4],
+    en_passant: [u64; 8],
+}
+
+// always the same numbers so keys can be saved and compared between runs.
+pub static KEYS: ZobristKeys = ZobristKeys::generate(0x7a3f_1c2e_9b4d_6e85);
+
+impl ZobristKeys {
+    const fn generate(seed: u64) -> ZobristKeys {
+        let mut state = seed;
+        let mut pieces = [[0; 64]; 12];
+        let mut piece = 0;
+        while piece < 12 {
+            let mut square = 0;
+            while square < 64 {
+                (state, pieces[piece][square]) = splitmix64(state);
+                square += 1;
+            }
+            piece += 1;
+        }
+        let (mut state, black_to_move) = splitmix64(state);
+        let mut castling = [0; 4];
+        let mut i = 0;
+        while i < 4 {
+            (state, castling[i]) = splitmix64(state);
+            i += 1;
+        }
+        let mut en_passant = [0; 8];
+        let mut i = 0;
+        while i < 8 {
+            (state, en_passant[i]) = splitmix64(state);
+            i += 1;
+        }
+        ZobristKeys {
+            pieces,
+            black_to_move,
+            castling,
+            en_passant,
+        }
+    }
+
+    pub fn piece(&self, piece: &Pieces, location: &RowColumn) -> u64 {
+        let index = color_index(piece.get_color()) * 6 + piece_index(&piece.get_piece_type());
+        self.pieces[index][square_index(location)]
+    }
+
+    pub fn side_to_move(&self, color: &Color) -> u64 {
+        match color {
+            Color::White => 0,
+            Color::Black => self.black_to_move,
+        }
+    }
+
+    /// Takes the castling rights the way `Game::get_castling_rights` writes them.
+    pub fn castling_rights(&self, rights: &str) -> u64 {
+        let mut key = 0;
+        for (i, right) in ['K', 'Q', 'k', 'q'].iter().enumerate() {
+            if rights.contains(*right) {
+                key ^= self.castling[i];
+            }
+        }
+        key
+    }
+
+    pub fn en_passant(&self, column: Option<u8>) -> u64 {
+        match column {
+            Some(column) => self.en_passant[column as usize],
+            None => 0,
+        }
+    }
+
+    /// The key of a whole position, counting every piece on the board.
+    pub fn position(
+        &self,
+        board: &Board,
+        current_color: &Color,
+        castling_rights: &str,
+        en_passant_column: Option<u8>,
+    ) -> u64 {
+        let mut key = 0;
+        for (row, squares) in board.squares.iter().enumerate() {
+            for (column, square) in squares.iter().enumerate() {
+                if let Some(piece) = square.get_piece() {
+                    key ^= self.piece(piece, &RowColumn::new(row as u8, column as u8));
+                }
+            }
+        }
+        key ^ self.side_to_move(current_color)
+            ^ self.castling_rights(castling_rights)
+            ^ self.en_passant(en_passant_column)
+    }
+}
+
+/// SplitMix64, returns the next state and the number it gives.
+const fn splitmix64(state: u64) -> (u64, u64) {
+    let state = state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+    let mut z = state;
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    (state, z ^ (z >> 31))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::game::zobrist::KEYS;
+
+    #[test]
+    fn keys_are_all_different() {
+        let mut keys = vec![KEYS.black_to_move];
+        for piece in KEYS.pieces.iter() {
+            keys.extend_from_slice(piece);
+        }
+        keys.extend_from_slice(&KEYS.castling);
+        keys.extend_from_slice(&KEYS.en_passant);
+        let count = keys.len();
+        keys.sort();
+        keys.dedup();
+        assert_eq!(count, keys.len());
+        assert_eq!(false, keys.contains(&0));
+    }
+}
@@ -1,4 +1,6 @@
 pub mod board_movements;
+pub mod console;
+pub mod engine;
 pub mod game;
 pub mod pieces;
 pub mod utils;
@@ -1,6 +1,26 @@
-use chess::game::game::Game;
+use std::env;
+use std::io;
+use std::process;
+
+use chess::console::console_game::ConsoleGame;
+use chess::console::players::Players;
+use chess::engine::xboard::Xboard;
 
 fn main() {
-    let mut game = Game::new();
-    game.read_input();
+    let options: Vec<String> = env::args().skip(1).collect();
+    // `chess xboard` is the engine for XBoard and other CECP GUIs.
+    if options.first().map(|option| option.as_str()) == Some("xboard") {
+        let mut xboard = Xboard::new(io::stdout());
+        xboard.run(io::stdin().lock());
+        return;
+    }
+    // same options as the new command, e.g. `chess black depth 4`.
+    let players = match Players::parse(options.iter().map(|option| option.as_str())) {
+        Ok(players) => players,
+        Err(error) => {
+            eprintln!("{}", error);
+            process::exit(1);
+        }
+    };
+    ConsoleGame::new(players).read_input();
 }
@@ -10,4 +10,11 @@ impl Color {
             Color::Black => String::from("B"),
         };
     }
+
+    pub fn opposite(&self) -> Color {
+        match self {
+            Color::White => Color::Black,
+            Color::Black => Color::White,
+        }
+    }
 }
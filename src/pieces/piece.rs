@@ -117,6 +117,50 @@ pub enum PieceType {
 }
 
 impl PieceType {
+    /// The pieces a pawn can be promoted to.
+    pub const PROMOTIONS: [PieceType; 4] = [
+        PieceType::Queen,
+        PieceType::Rook,
+        PieceType::Bishop,
+        PieceType::Knight,
+    ];
+
+    pub fn get_name(&self) -> char {
+        match self {
+            PieceType::Pawn => 'P',
+            PieceType::Bishop => 'B',
+            PieceType::Knight => 'N',
+            PieceType::King => 'K',
+            PieceType::Queen => 'Q',
+            PieceType::Rook => 'R',
+        }
+    }
+
+    /// What the piece is worth in centipawns. The king is worth more than everything else
+    /// together so trading it is never a good deal.
+    pub fn get_value(&self) -> i32 {
+        match self {
+            PieceType::Pawn => 100,
+            PieceType::Knight => 320,
+            PieceType::Bishop => 330,
+            PieceType::Rook => 500,
+            PieceType::Queen => 900,
+            PieceType::King => 20_000,
+        }
+    }
+
+    pub fn from_char(c: char) -> Option<PieceType> {
+        match c.to_ascii_uppercase() {
+            'P' => Some(PieceType::Pawn),
+            'B' => Some(PieceType::Bishop),
+            'N' => Some(PieceType::Knight),
+            'K' => Some(PieceType::King),
+            'Q' => Some(PieceType::Queen),
+            'R' => Some(PieceType::Rook),
+            _ => None,
+        }
+    }
+
     pub fn create_actual_piece(&self, piece_info: PieceInfo) -> Pieces {
         match self {
             PieceType::Pawn => Pieces::Pawn(piece_info),
@@ -274,7 +318,8 @@ impl Pieces {
                     pawn.get_has_moved(),
                     last_history,
                 );
-                if validator.validate() {
+                // can_move so a pawn can't jump over a piece on its first move.
+                if validator.validate() && validator.can_move(board) {
                     if to.row == 7 || to.row == 0 {
                         return ValidMovement::Promotion;
                     }
@@ -303,9 +348,24 @@ impl Pieces {
                         ))
                     })
                 {
-                    if matches!(piece_type, PieceType::Rook) {
+                    let king_color = king_info.get_color();
+                    let rook_color = board.squares[position_of_rook.from.row as usize]
+                        [position_of_rook.from.column as usize]
+                        .get_color_of_piece();
+                    if matches!(piece_type, PieceType::Rook)
+                        && rook_color.as_ref() == Some(king_color)
+                    {
                         let validator = RookValidator::new(position_of_rook.clone());
-                        if !piece_info.get_has_moved() && validator.can_move(&board) {
+                        // the king can't castle out of check or through a square that's attacked.
+                        // the square it lands on is checked like any other move.
+                        let passing_square =
+                            RowColumn::new(from.row, (from.column + to.column) / 2);
+                        let opponent = king_color.opposite();
+                        if !piece_info.get_has_moved()
+                            && validator.can_move(&board)
+                            && !board.is_square_attacked(from, &opponent)
+                            && !board.is_square_attacked(&passing_square, &opponent)
+                        {
                             return ValidMovement::CASTLING(position_of_rook.clone());
                         }
                     }
@@ -51,10 +51,13 @@ impl<'a, T: BaseValidator> BaseValidator for PawnValidator<'a, T> {
         if self.to_has_piece {
             let is_column_movement_valid =
                 current_column as i8 - 1 == to_column as i8 || current_column + 1 == to_column;
-            return (matches!(self.color, Color::White)
-                && current_row - 1 == to_row
-                && (is_column_movement_valid))
-                || (current_row + 1 == to_row && (is_column_movement_valid));
+            // white moves up the board(towards row 0), black moves down.
+            let forward_row = if matches!(self.color, Color::White) {
+                current_row as i8 - 1
+            } else {
+                current_row as i8 + 1
+            };
+            return forward_row == to_row as i8 && is_column_movement_valid;
         }
         // can't be the same.
         let mut movement = 1i8;
@@ -103,6 +106,12 @@ impl<'a, T: BaseValidator> PawnValidator<'a, T> {
                 if col_diff != 0i8 {
                     return false;
                 }
+                // only a pawn that just moved two squares can be taken en passant
+                let last_row_diff =
+                    (last_history.get_from().row as i8 - last_history.get_to().row as i8).abs();
+                if last_row_diff != 2 || self.get_from().row != last_history.get_to().row {
+                    return false;
+                }
                 let is_neighbor =
                     (self.get_from().column as i8 - last_history.get_to().column as i8).abs();
                 if is_neighbor != 1 {
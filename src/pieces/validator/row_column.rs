@@ -1,3 +1,5 @@
+use std::fmt;
+
 #[derive(Clone, Eq, PartialEq, Debug)]
 pub struct RowColumn {
     pub row: u8,
@@ -9,6 +11,13 @@ impl RowColumn {
     }
 }
 
+/// Prints the square the way a player would type it, e.g. row 7 column 0 is `a1`.
+impl fmt::Display for RowColumn {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}{}", (b'a' + self.column) as char, 8 - self.row)
+    }
+}
+
 #[derive(Clone)]
 pub struct BaseLocation {
     pub from: RowColumn,
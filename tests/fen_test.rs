@@ -0,0 +1,78 @@
+use chess::game::game::Game;
+
+/// xorshift, so the random games are the same on every run.
+struct Random(u64);
+
+impl Random {
+    fn next(&mut self, bound: usize) -> usize {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        (self.0 % bound as u64) as usize
+    }
+}
+
+#[test]
+fn fen_round_trips_through_random_games() {
+    let mut random = Random(0x2545_f491_4f6c_dd1d);
+    for _ in 0..20 {
+        let mut game = Game::new();
+        for _ in 0..80 {
+            let fen = game.to_fen();
+            let imported = Game::from_fen(&fen).unwrap();
+            assert_eq!(fen, imported.to_fen());
+            // the key kept up to date move by move matches the one of the whole position.
+            assert_eq!(game.get_hash(), imported.get_hash(), "{}", fen);
+            assert_eq!(game.legal_moves(), imported.legal_moves(), "{}", fen);
+            assert_eq!(game.get_result(), imported.get_result(), "{}", fen);
+
+            let moves = game.legal_moves();
+            if game.is_over() || moves.is_empty() {
+                break;
+            }
+            let movement = &moves[random.next(moves.len())];
+            game.play_movement(movement).unwrap();
+        }
+    }
+}
+
+#[test]
+fn san_round_trips_through_random_games() {
+    let mut random = Random(0x9e37_79b9_7f4a_7c15);
+    for _ in 0..20 {
+        let mut game = Game::new();
+        for _ in 0..80 {
+            let moves = game.legal_moves();
+            if game.is_over() || moves.is_empty() {
+                break;
+            }
+            let movement = &moves[random.next(moves.len())];
+            let san = game.to_san(movement);
+            assert_eq!(Ok(movement.clone()), game.parse_san(&san), "{}", san);
+            game.play_movement(movement).unwrap();
+        }
+    }
+}
+
+#[test]
+fn undo_takes_random_games_back_to_the_start() {
+    let mut random = Random(0x1234_5678_9abc_def1);
+    for _ in 0..10 {
+        let mut game = Game::new();
+        let mut fens = vec![];
+        for _ in 0..80 {
+            let moves = game.legal_moves();
+            if game.is_over() || moves.is_empty() {
+                break;
+            }
+            fens.push((game.to_fen(), game.get_hash()));
+            game.play_movement(&moves[random.next(moves.len())])
+                .unwrap();
+        }
+        while let Some((fen, hash)) = fens.pop() {
+            game.undo().unwrap();
+            assert_eq!(fen, game.to_fen());
+            assert_eq!(hash, game.get_hash());
+        }
+    }
+}
@@ -1,244 +1,89 @@
 use chess::game::game::Game;
-use std::fs::File;
-use std::io;
-use std::io::BufRead;
-use std::path::Path;
+use chess::game::game_result::{EndReason, GameResult};
+use chess::game::pgn::{parse_pgn, PgnGame};
+use std::fs;
 
-fn read_lines<P>(filename: P) -> io::Result<io::Lines<io::BufReader<File>>>
-where
-    P: AsRef<Path>,
-{
-    let file = File::open(filename)?;
-    Ok(io::BufReader::new(file).lines())
+fn replay(filename: &str) -> (PgnGame, Game) {
+    let pgn = fs::read_to_string(format!("./tests/resources/{}", filename)).unwrap();
+    let mut games = parse_pgn(&pgn).unwrap();
+    assert_eq!(1, games.len());
+    let pgn_game = games.remove(0);
+    let game = match pgn_game.replay() {
+        Ok(game) => game,
+        Err(error) => panic!("{}", error),
+    };
+    (pgn_game, game)
 }
+
 #[test]
 fn playing_against_myself_before_check_mate_logic() {
-    let mut game = Game::new();
-    if let Ok(lines) = read_lines("./tests/resources/black_wins.txt") {
-        for line in lines {
-            match line {
-                Ok(line) => {
-                    let movement: Vec<&str> = line.split(" ").collect();
-                    if movement.len() == 3 {
-                        let mut sb = String::from("move ");
-                        sb.push_str(movement[1]);
-                        sb.push_str(" ");
-                        sb.push_str(movement[2]);
-                        let result = game.handle_input(&sb);
-                        assert_eq!(true, result.is_ok(), "failed at {}", sb);
-                    }
-                }
-                Err(error) => {
-                    panic!("{}", error)
-                }
-            }
-        }
-    }
+    let (pgn_game, game) = replay("black_wins.pgn");
+    assert_eq!(Some("0-1"), pgn_game.result.as_deref());
+    assert_eq!(
+        Some(&GameResult::BlackWins(EndReason::Checkmate)),
+        game.get_result()
+    );
     game.print_board();
 }
 
 #[test]
 fn draw() {
-    let mut game = Game::new();
-    if let Ok(lines) = read_lines("./tests/resources/draw.txt") {
-        for line in lines {
-            match line {
-                Ok(line) => {
-                    if line.len() == 4 {
-                        let mut sb = String::from("move ");
-                        let mut iter = line.chars();
-
-                        sb.push(iter.next().unwrap());
-                        sb.push(iter.next().unwrap());
-                        sb.push_str(" ");
-                        sb.push(iter.next().unwrap());
-                        sb.push(iter.next().unwrap());
-                        let result = game.handle_input(&sb);
-                        assert_eq!(true, result.is_ok(), "failed at {}", sb);
-                    }
-                }
-                Err(error) => {
-                    panic!("{}", error)
-                }
-            }
-        }
-    }
+    let (pgn_game, game) = replay("draw.pgn");
+    assert_eq!(Some("1/2-1/2"), pgn_game.result.as_deref());
+    // ends with the same position three times, which was claimed.
+    assert_eq!(
+        Some(&GameResult::Draw(EndReason::ThreefoldRepetition)),
+        game.get_result()
+    );
     game.print_board();
 }
 
 // features promotion
 #[test]
 fn white_wins() {
-    let mut game = Game::new();
-    if let Ok(lines) = read_lines("./tests/resources/white_wins.txt") {
-        for line in lines {
-            match line {
-                Ok(line) => {
-                    if line.len() >= 4 {
-                        let mut sb = String::from("move ");
-                        let mut iter = line.chars();
-
-                        sb.push(iter.next().unwrap());
-                        sb.push(iter.next().unwrap());
-                        sb.push_str(" ");
-                        sb.push(iter.next().unwrap());
-                        sb.push(iter.next().unwrap());
-                        if line.len() == 5 {
-                            sb.push(iter.next().unwrap());
-                        }
-                        let result = game.handle_input(&sb);
-                        assert_eq!(true, result.is_ok(), "failed at {}", sb);
-                    }
-                }
-                Err(error) => {
-                    panic!("{}", error)
-                }
-            }
-        }
-    }
+    let (pgn_game, game) = replay("white_wins.pgn");
+    assert_eq!(Some("1-0"), pgn_game.result.as_deref());
+    assert_eq!(
+        Some(&GameResult::WhiteWins(EndReason::Checkmate)),
+        game.get_result()
+    );
     game.print_board();
 }
 
 #[test]
 fn white_wins_aug_30() {
-    let mut game = Game::new();
-    if let Ok(lines) = read_lines("./tests/resources/white_wins_august_30.txt") {
-        for line in lines {
-            match line {
-                Ok(line) => {
-                    if line.len() >= 4 {
-                        let mut sb = String::from("move ");
-                        let mut iter = line.chars();
-
-                        sb.push(iter.next().unwrap());
-                        sb.push(iter.next().unwrap());
-                        sb.push_str(" ");
-                        sb.push(iter.next().unwrap());
-                        sb.push(iter.next().unwrap());
-                        if line.len() == 5 {
-                            sb.push(iter.next().unwrap());
-                        }
-                        let result = game.handle_input(&sb);
-                        assert_eq!(true, result.is_ok(), "failed at {}", sb);
-                        if sb == "move c6 b5" {
-                            game.print_board();
-                            print!("check here");
-                        }
-                    }
-                }
-                Err(error) => {
-                    panic!("{}", error)
-                }
-            }
-        }
-    }
+    let (_, game) = replay("white_wins_august_30.pgn");
+    assert_eq!(
+        Some(&GameResult::WhiteWins(EndReason::Checkmate)),
+        game.get_result()
+    );
     game.print_board();
 }
 
 #[test]
 fn draw_aug_30() {
-    let mut game = Game::new();
-    if let Ok(lines) = read_lines("./tests/resources/draw_august_30.txt") {
-        for line in lines {
-            match line {
-                Ok(line) => {
-                    if line.len() >= 4 {
-                        // if line == "g7g8Q" {
-                        //     print!("check here");
-                        // }
-                        let mut sb = String::from("move ");
-                        let mut iter = line.chars();
-
-                        sb.push(iter.next().unwrap());
-                        sb.push(iter.next().unwrap());
-                        sb.push_str(" ");
-                        sb.push(iter.next().unwrap());
-                        sb.push(iter.next().unwrap());
-                        if line.len() == 5 {
-                            sb.push(iter.next().unwrap());
-                        }
-                        let result = game.handle_input(&sb);
-                        assert_eq!(true, result.is_ok(), "failed at {}", sb);
-                    }
-                }
-                Err(error) => {
-                    panic!("{}", error)
-                }
-            }
-        }
-    }
+    let (pgn_game, game) = replay("draw_august_30.pgn");
+    assert_eq!(Some("*"), pgn_game.result.as_deref());
+    assert_eq!(None, game.get_result());
     game.print_board();
 }
 
 #[test]
 fn draw_sept_1() {
-    let mut game = Game::new();
-    if let Ok(lines) = read_lines("./tests/resources/draw_sept_1.txt") {
-        for line in lines {
-            match line {
-                Ok(line) => {
-                    if line.len() >= 4 {
-                        let mut sb = String::from("move ");
-                        let mut iter = line.chars();
-
-                        sb.push(iter.next().unwrap());
-                        sb.push(iter.next().unwrap());
-                        sb.push_str(" ");
-                        sb.push(iter.next().unwrap());
-                        sb.push(iter.next().unwrap());
-                        let mut check = false;
-                        if line.len() == 5 {
-                            let next_char = iter.next().unwrap();
-                            if next_char != '+' {
-                                sb.push(next_char);
-                            } else {
-                                check = true;
-                            }
-                        }
-                        let result = game.handle_input(&sb);
-                        assert_eq!(true, result.is_ok(), "failed at {}", sb);
-                    }
-                }
-                Err(error) => {
-                    panic!("{}", error)
-                }
-            }
-        }
-    }
+    let (_, game) = replay("draw_sept_1.pgn");
+    assert_eq!(
+        Some(&GameResult::Draw(EndReason::ThreefoldRepetition)),
+        game.get_result()
+    );
     game.print_board();
 }
 
 #[test]
 fn white_wins_sept_1() {
-    let mut game = Game::new();
-    if let Ok(lines) = read_lines("./tests/resources/white_wins_sept_1.txt") {
-        for line in lines {
-            match line {
-                Ok(line) => {
-                    if line.len() >= 4 {
-                        let mut sb = String::from("move ");
-                        let mut iter = line.chars();
-
-                        sb.push(iter.next().unwrap());
-                        sb.push(iter.next().unwrap());
-                        sb.push_str(" ");
-                        sb.push(iter.next().unwrap());
-                        sb.push(iter.next().unwrap());
-                        if line.len() == 5 {
-                            let next_char = iter.next().unwrap();
-                            if next_char != '+' {
-                                sb.push(next_char);
-                            }
-                        }
-                        let result = game.handle_input(&sb);
-                        assert_eq!(true, result.is_ok(), "failed at {}", sb);
-                    }
-                }
-                Err(error) => {
-                    panic!("{}", error)
-                }
-            }
-        }
-    }
+    let (_, game) = replay("white_wins_sept_1.pgn");
+    assert_eq!(
+        Some(&GameResult::WhiteWins(EndReason::Checkmate)),
+        game.get_result()
+    );
     game.print_board();
 }
@@ -0,0 +1,42 @@
+use std::io::{BufRead, BufReader, Write};
+use std::process::{Command, Stdio};
+
+// talks to the engine binary the way a GUI would.
+#[test]
+fn plays_a_game_over_uci() {
+    let mut engine = Command::new(env!("CARGO_BIN_EXE_chess-uci"))
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .unwrap();
+    let mut input = engine.stdin.take().unwrap();
+    let mut output = BufReader::new(engine.stdout.take().unwrap()).lines();
+    let mut read_until = |prefix: &str| loop {
+        let line = output.next().unwrap().unwrap();
+        if line.starts_with(prefix) {
+            return line;
+        }
+    };
+
+    writeln!(input, "uci").unwrap();
+    read_until("uciok");
+    writeln!(input, "isready").unwrap();
+    read_until("readyok");
+
+    let mut moves: Vec<String> = vec![];
+    for _ in 0..6 {
+        writeln!(input, "position startpos moves {}", moves.join(" ")).unwrap();
+        writeln!(input, "go depth 2").unwrap();
+        let best_move = read_until("bestmove ");
+        moves.push(best_move["bestmove ".len()..].to_string());
+    }
+    writeln!(input, "quit").unwrap();
+    assert!(engine.wait().unwrap().success());
+
+    // every move the engine sent has to be legal.
+    let mut game = chess::game::game::Game::new();
+    for notation in &moves {
+        let movement = game.parse_coordinate_notation(notation).unwrap();
+        game.play_movement(&movement).unwrap();
+    }
+}